@@ -0,0 +1,206 @@
+use crate::matrix::Matrix;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug)]
+pub struct Aabb {
+    pub min: Vector,
+    pub max: Vector,
+}
+
+// Construction
+impl Aabb {
+    pub fn new(min: Vector, max: Vector) -> Aabb {
+        Aabb { min, max }
+    }
+
+    /// Smallest box containing both `a` and `b`.
+    pub fn surrounding(a: &Aabb, b: &Aabb) -> Aabb {
+        Aabb {
+            min: Vector::new(
+                a.min.x.min(b.min.x),
+                a.min.y.min(b.min.y),
+                a.min.z.min(b.min.z),
+            ),
+            max: Vector::new(
+                a.max.x.max(b.max.x),
+                a.max.y.max(b.max.y),
+                a.max.z.max(b.max.z),
+            ),
+        }
+    }
+
+    /// Largest box contained in both `a` and `b`.
+    pub fn overlapping(a: &Aabb, b: &Aabb) -> Aabb {
+        Aabb {
+            min: Vector::new(
+                a.min.x.max(b.min.x),
+                a.min.y.max(b.min.y),
+                a.min.z.max(b.min.z),
+            ),
+            max: Vector::new(
+                a.max.x.min(b.max.x),
+                a.max.y.min(b.max.y),
+                a.max.z.min(b.max.z),
+            ),
+        }
+    }
+}
+
+// Computed properties
+impl Aabb {
+    pub fn centroid(&self) -> Vector {
+        0.5 * (self.min + self.max)
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// Index of the axis along which the box is longest.
+    pub fn longest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+// Box-specific operations
+impl Aabb {
+    /// Slab test for whether the ray passes through the box between `t_min`
+    /// and `t_max`.
+    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.clip(r, t_min, t_max).is_some()
+    }
+
+    /// Part of the range `t_min` to `t_max` which the ray spends inside the
+    /// box, if any.
+    pub fn clip(&self, r: &Ray, mut t_min: f64, mut t_max: f64) -> Option<(f64, f64)> {
+        for a in 0..3 {
+            let inv_d = 1.0 / r.direction[a];
+            let mut t0 = (self.min[a] - r.origin[a]) * inv_d;
+            let mut t1 = (self.max[a] - r.origin[a]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max < t_min {
+                return None;
+            }
+        }
+        Some((t_min, t_max))
+    }
+
+    /// Bounding box of this box after an affine transformation.
+    pub fn transform(&self, transform: &Matrix) -> Aabb {
+        let mut result: Option<Aabb> = None;
+        for i in 0..8 {
+            let corner = Vector::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            );
+            let p = transform.transform_point(&corner);
+            let point = Aabb::new(p, p);
+            result = Some(match result {
+                None => point,
+                Some(bounds) => Aabb::surrounding(&bounds, &point),
+            });
+        }
+        result.unwrap()
+    }
+
+    pub fn translate(&self, offset: Vector) -> Aabb {
+        Aabb::new(self.min + offset, self.max + offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    // Utility function to check the approximate equality of two vectors.
+    //
+    // Direct equality comparison between two vectors is unavailable due to
+    // the underlying float representation.
+    fn vec_approx_equal(v1: Vector, v2: Vector) {
+        assert_approx_eq!(v1.x, v2.x);
+        assert_approx_eq!(v1.y, v2.y);
+        assert_approx_eq!(v1.z, v2.z);
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb::new(Vector::new(0.0, 0.0, 0.0), Vector::new(1.0, 1.0, 1.0))
+    }
+
+    // Construction
+
+    #[test]
+    fn aabb_surrounding() {
+        let other = Aabb::new(Vector::new(-1.0, 0.5, 0.5), Vector::new(0.5, 2.0, 0.5));
+        let given = Aabb::surrounding(&unit_box(), &other);
+        vec_approx_equal(given.min, Vector::new(-1.0, 0.0, 0.0));
+        vec_approx_equal(given.max, Vector::new(1.0, 2.0, 1.0));
+    }
+
+    #[test]
+    fn aabb_overlapping() {
+        let other = Aabb::new(Vector::new(-1.0, 0.5, 0.5), Vector::new(0.5, 2.0, 0.5));
+        let given = Aabb::overlapping(&unit_box(), &other);
+        vec_approx_equal(given.min, Vector::new(0.0, 0.5, 0.5));
+        vec_approx_equal(given.max, Vector::new(0.5, 1.0, 0.5));
+    }
+
+    // Computed properties
+
+    #[test]
+    fn aabb_computed_properties() {
+        let given = Aabb::new(Vector::new(0.0, 0.0, 0.0), Vector::new(1.0, 3.0, 2.0));
+        vec_approx_equal(given.centroid(), Vector::new(0.5, 1.5, 1.0));
+        assert_approx_eq!(given.surface_area(), 22.0);
+        assert_eq!(given.longest_axis(), 1);
+    }
+
+    // Box-specific operations
+
+    #[test]
+    fn aabb_hit() {
+        let given = unit_box();
+        let r = Ray::new(Vector::new(0.5, 0.5, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(given.hit(&r, 0.0, f64::MAX));
+        assert!(!given.hit(&r, 0.0, 3.0));
+
+        let r = Ray::new(Vector::new(2.0, 0.5, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(!given.hit(&r, 0.0, f64::MAX));
+    }
+
+    #[test]
+    fn aabb_clip() {
+        let given = unit_box();
+        let r = Ray::new(Vector::new(0.5, 0.5, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let (t0, t1) = given.clip(&r, 0.0, f64::MAX).unwrap();
+        assert_approx_eq!(t0, 4.0);
+        assert_approx_eq!(t1, 5.0);
+
+        let (t0, t1) = given.clip(&r, 4.5, 4.75).unwrap();
+        assert_approx_eq!(t0, 4.5);
+        assert_approx_eq!(t1, 4.75);
+    }
+
+    #[test]
+    fn aabb_transform() {
+        let transform = Matrix::rotation(Vector::new(0.0, 0.0, 1.0), 90.0);
+        let given = unit_box().transform(&transform);
+        vec_approx_equal(given.min, Vector::new(-1.0, 0.0, 0.0));
+        vec_approx_equal(given.max, Vector::new(0.0, 1.0, 1.0));
+    }
+}
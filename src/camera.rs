@@ -1,8 +1,16 @@
 use std::f64::consts::PI;
 
+use rand::Rng;
+
+use crate::objects::Object;
 use crate::ray::Ray;
 use crate::vector::Vector;
 
+mod equirectangular;
+mod fisheye;
+mod orthographic;
+mod perspective;
+
 pub struct Orientation {
     pub look_from: Vector,
     pub look_at: Vector,
@@ -19,83 +27,511 @@ impl Orientation {
     }
 }
 
+/// How the focus distance of a lens is chosen.
+#[derive(Copy, Clone, Debug)]
+pub enum Focus {
+    /// Focus at a fixed distance from the camera.
+    Distance(f64),
+    /// Focus on whatever is visible through the image coordinates `(s, t)`.
+    Auto { s: f64, t: f64 },
+}
+
+impl Focus {
+    /// Focus on whatever is visible at the centre of the image.
+    pub fn auto() -> Focus {
+        Focus::Auto { s: 0.5, t: 0.5 }
+    }
+}
+
 pub struct Lens {
     pub v_fov: f64,
     pub aperture: f64,
-    pub focus_dist: f64,
+    pub focus: Focus,
+    /// Known only for lenses constructed from physical parameters.
+    pub f_stop: Option<f64>,
+    pub exposure: Option<Exposure>,
 }
 
 impl Lens {
-    pub fn new(v_fov: f64, aperture: f64, focus_dist: f64) -> Lens {
+    pub fn new(v_fov: f64, aperture: f64, focus: Focus) -> Lens {
+        Lens {
+            v_fov,
+            aperture,
+            focus,
+            f_stop: None,
+            exposure: None,
+        }
+    }
+
+    /// Construct a lens from a focal length in millimetres, the sensor it
+    /// projects onto and an f-stop. Scene units are taken to be metres.
+    pub fn from_physical(focal_length: f64, sensor: Sensor, f_stop: f64, focus: Focus) -> Lens {
+        let v_fov = 2.0 * (sensor.height / (2.0 * focal_length)).atan() * (180.0 / PI);
+        let aperture = focal_length / f_stop / 1000.0;
         Lens {
             v_fov,
             aperture,
-            focus_dist,
+            focus,
+            f_stop: Some(f_stop),
+            exposure: None,
+        }
+    }
+
+    /// Scale the brightness of the image as a physical camera with this lens
+    /// would under the given exposure settings.
+    pub fn with_exposure(self, exposure: Exposure) -> Lens {
+        assert!(
+            self.f_stop.is_some(),
+            "Exposure requires a lens with an f-stop!"
+        );
+        Lens {
+            exposure: Some(exposure),
+            ..self
+        }
+    }
+
+    /// Factor applied to the brightness of the image, which is left unchanged
+    /// without exposure settings.
+    pub fn exposure(&self) -> f64 {
+        match (self.f_stop, self.exposure) {
+            (Some(f_stop), Some(exposure)) => exposure.multiplier(f_stop),
+            _ => 1.0,
         }
     }
 }
 
-pub struct Camera {
+/// Dimensions of a camera sensor in millimetres.
+#[derive(Copy, Clone, Debug)]
+pub struct Sensor {
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Sensor {
+    pub fn new(width: f64, height: f64) -> Sensor {
+        Sensor { width, height }
+    }
+
+    /// 36 x 24 mm "full frame" sensor.
+    pub fn full_frame() -> Sensor {
+        Sensor::new(36.0, 24.0)
+    }
+
+    pub fn aspect(&self) -> f64 {
+        self.width / self.height
+    }
+}
+
+// Exposure value (at ISO 100) under which scene brightness is unchanged,
+// close to that of f/8 at 1/125 s
+const REFERENCE_EV100: f64 = 13.0;
+
+/// Exposure settings of a physical camera, whose f-stop is that of its lens.
+#[derive(Copy, Clone, Debug)]
+pub struct Exposure {
+    pub shutter_speed: f64,
+    pub iso: f64,
+}
+
+impl Exposure {
+    pub fn new(shutter_speed: f64, iso: f64) -> Exposure {
+        Exposure { shutter_speed, iso }
+    }
+
+    /// Factor scaling scene brightness through a lens of the given `f_stop`,
+    /// doubling for each stop of light more than the reference exposure.
+    pub fn multiplier(&self, f_stop: f64) -> f64 {
+        let ev100 = (f_stop * f_stop / self.shutter_speed * 100.0 / self.iso).log2();
+        2.0_f64.powf(REFERENCE_EV100 - ev100)
+    }
+}
+
+/// Radial mapping used by a fisheye projection, relating the angle from the
+/// optical axis to the distance from the centre of the image circle.
+#[derive(Copy, Clone, Debug)]
+pub enum Mapping {
+    Equidistant,
+    Equisolid,
+}
+
+// Camera position and orthonormal basis shared by every projection.
+struct Frame {
     origin: Vector,
-    lower_left_corner: Vector,
-    horizontal: Vector,
-    vertical: Vector,
     u: Vector,
     v: Vector,
-    lens_radius: f64,
+    w: Vector,
 }
 
-impl Camera {
-    pub fn new(orientation: Orientation, lens: Lens, aspect: f64) -> Camera {
-        // Redefine inputs
-        let look_from = orientation.look_from;
-        let look_at = orientation.look_at;
-        let v_up = orientation.v_up;
+impl Frame {
+    fn new(orientation: &Orientation) -> Frame {
+        let w = (orientation.look_from - orientation.look_at).unit();
+        let u = Vector::cross(&orientation.v_up, &w).unit();
+        let v = Vector::cross(&w, &u);
+        Frame {
+            origin: orientation.look_from,
+            u,
+            v,
+            w,
+        }
+    }
+}
+
+enum Projection {
+    Perspective {
+        half_width: f64,
+        half_height: f64,
+        lens_radius: f64,
+        focus_dist: f64,
+        autofocus: Option<(f64, f64)>,
+    },
+    Orthographic {
+        half_width: f64,
+        half_height: f64,
+    },
+    Fisheye {
+        half_fov: f64,
+        aspect: f64,
+        mapping: Mapping,
+    },
+    Equirectangular,
+}
+
+/// Interval of time over which the shutter is open.
+#[derive(Copy, Clone, Debug)]
+pub struct Shutter {
+    pub open: f64,
+    pub close: f64,
+}
 
-        let v_fov = lens.v_fov;
-        let aperture = lens.aperture;
-        let focus_dist = lens.focus_dist;
+impl Shutter {
+    pub fn new(open: f64, close: f64) -> Shutter {
+        Shutter { open, close }
+    }
 
+    /// Pick a random time while the shutter is open.
+    pub fn sample(&self) -> f64 {
+        let mut rng = rand::thread_rng();
+        self.open + rng.gen::<f64>() * (self.close - self.open)
+    }
+}
+
+pub struct Camera {
+    frame: Frame,
+    projection: Projection,
+    shutter: Shutter,
+    exposure: f64,
+}
+
+impl Camera {
+    /// Thin-lens perspective camera.
+    pub fn new(orientation: Orientation, lens: Lens, aspect: f64) -> Camera {
         // Convert FOV to radians
-        let theta = v_fov * (PI / 180.0);
+        let theta = lens.v_fov * (PI / 180.0);
 
         // Define FOV in both dimensions of image
         let half_height = (theta / 2.0).tan();
         let half_width = aspect * half_height;
 
-        // Define orthonormal basis for camera orientation
-        let w = (look_from - look_at).unit();
-        let u = Vector::cross(&v_up, &w).unit();
-        let v = Vector::cross(&w, &u);
+        // Autofocus starts at the look at point until the world is known
+        let (focus_dist, autofocus) = match lens.focus {
+            Focus::Distance(focus_dist) => (focus_dist, None),
+            Focus::Auto { s, t } => (
+                (orientation.look_from - orientation.look_at).length(),
+                Some((s, t)),
+            ),
+        };
 
-        // Important locations
-        let origin = look_from;
-        let lower_left_corner =
-            origin - half_width * focus_dist * u - half_height * focus_dist * v - focus_dist * w;
-        let horizontal = 2.0 * half_width * focus_dist * u;
-        let vertical = 2.0 * half_height * focus_dist * v;
+        Camera {
+            frame: Frame::new(&orientation),
+            projection: Projection::Perspective {
+                half_width,
+                half_height,
+                lens_radius: lens.aperture / 2.0,
+                focus_dist,
+                autofocus,
+            },
+            shutter: Shutter::new(0.0, 0.0),
+            exposure: lens.exposure(),
+        }
+    }
 
-        // Define lens radius
-        let lens_radius = aperture / 2.0;
+    /// Orthographic camera whose view spans `height` world units vertically.
+    pub fn new_orthographic(orientation: Orientation, height: f64, aspect: f64) -> Camera {
+        let half_height = height / 2.0;
+        let half_width = aspect * half_height;
 
         Camera {
-            origin,
-            lower_left_corner,
-            horizontal,
-            vertical,
-            u,
-            v,
-            lens_radius,
+            frame: Frame::new(&orientation),
+            projection: Projection::Orthographic {
+                half_width,
+                half_height,
+            },
+            shutter: Shutter::new(0.0, 0.0),
+            exposure: 1.0,
+        }
+    }
+
+    /// Fisheye camera whose image circle spans `fov` degrees and touches the
+    /// top and bottom of the image.
+    pub fn new_fisheye(
+        orientation: Orientation,
+        fov: f64,
+        mapping: Mapping,
+        aspect: f64,
+    ) -> Camera {
+        Camera {
+            frame: Frame::new(&orientation),
+            projection: Projection::Fisheye {
+                half_fov: fov * (PI / 180.0) / 2.0,
+                aspect,
+                mapping,
+            },
+            shutter: Shutter::new(0.0, 0.0),
+            exposure: 1.0,
+        }
+    }
+
+    /// 360° equirectangular panorama camera centred on `look_at`.
+    pub fn new_equirectangular(orientation: Orientation) -> Camera {
+        Camera {
+            frame: Frame::new(&orientation),
+            projection: Projection::Equirectangular,
+            shutter: Shutter::new(0.0, 0.0),
+            exposure: 1.0,
         }
     }
 
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        // Generate a random position on a disk around the origin
-        let rd = self.lens_radius * Vector::random();
-        let offset = self.u * rd.x + self.v * rd.y;
-        Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin - offset,
+    /// Keep the shutter open between the times `open` and `close`, so that
+    /// moving objects are blurred. The shutter is instantaneous by default.
+    pub fn with_shutter(self, open: f64, close: f64) -> Camera {
+        Camera {
+            shutter: Shutter::new(open, close),
+            ..self
+        }
+    }
+
+    pub fn shutter(&self) -> Shutter {
+        self.shutter
+    }
+
+    /// Factor applied to the brightness of the image, set by the exposure of
+    /// the lens.
+    pub fn exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    /// Resolve automatic focus by tracing a ray from the centre of the lens
+    /// through the autofocus point and focusing at the nearest hit in
+    /// `world`. The focus distance is left unchanged if nothing is hit.
+    pub fn autofocus(&mut self, world: &Object) {
+        if let Projection::Perspective {
+            half_width,
+            half_height,
+            ref mut focus_dist,
+            autofocus: Some((s, t)),
+            ..
+        } = self.projection
+        {
+            let r = perspective::get_ray(
+                &self.frame,
+                half_width,
+                half_height,
+                0.0,
+                1.0,
+                s,
+                t,
+                self.shutter.open,
+            );
+            if let Some(hit) = world.hit(&r, 0.0001, f64::MAX) {
+                // The ray direction has unit length along the view axis, so
+                // the ray parameter is also the depth of the hit
+                *focus_dist = hit.t;
+            }
+        }
+    }
+
+    /// Generate a ray through the image coordinates `(s, t)`, each in
+    /// `[0, 1]`. Returns `None` for points the projection does not cover.
+    pub fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let time = self.shutter.sample();
+        match self.projection {
+            Projection::Perspective {
+                half_width,
+                half_height,
+                lens_radius,
+                focus_dist,
+                ..
+            } => Some(perspective::get_ray(
+                &self.frame,
+                half_width,
+                half_height,
+                lens_radius,
+                focus_dist,
+                s,
+                t,
+                time,
+            )),
+            Projection::Orthographic {
+                half_width,
+                half_height,
+            } => Some(orthographic::get_ray(
+                &self.frame,
+                half_width,
+                half_height,
+                s,
+                t,
+                time,
+            )),
+            Projection::Fisheye {
+                half_fov,
+                aspect,
+                mapping,
+            } => fisheye::get_ray(&self.frame, half_fov, aspect, mapping, s, t, time),
+            Projection::Equirectangular => Some(equirectangular::get_ray(&self.frame, s, t, time)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+    use assert_approx_eq::assert_approx_eq;
+
+    // Utility function to check the approximate equality of two vectors.
+    //
+    // Direct equality comparison between two vectors is unavailable due to
+    // the underlying float representation.
+    fn vec_approx_equal(v1: Vector, v2: Vector) {
+        assert_approx_eq!(v1.x, v2.x);
+        assert_approx_eq!(v1.y, v2.y);
+        assert_approx_eq!(v1.z, v2.z);
+    }
+
+    fn orientation() -> Orientation {
+        Orientation::new(
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 1.0, 0.0),
         )
     }
+
+    // Physical parameters
+
+    #[test]
+    fn lens_from_physical() {
+        let lens = Lens::from_physical(50.0, Sensor::full_frame(), 2.0, Focus::Distance(5.0));
+        assert_approx_eq!(lens.v_fov, 26.991466);
+        assert_approx_eq!(lens.aperture, 0.025);
+    }
+
+    #[test]
+    fn exposure_multiplier() {
+        // Typical settings leave the brightness nearly unchanged
+        let given = Exposure::new(1.0 / 125.0, 100.0).multiplier(8.0);
+        assert_approx_eq!(given, 8192.0 / 8000.0);
+
+        // One stop less light for each halving of the shutter speed
+        let given = Exposure::new(1.0 / 250.0, 100.0).multiplier(8.0);
+        assert_approx_eq!(given, 4096.0 / 8000.0);
+
+        let given = Exposure::new(1.0 / 125.0, 400.0).multiplier(4.0);
+        assert_approx_eq!(given, 8192.0 / 500.0);
+    }
+
+    #[test]
+    fn lens_exposure() {
+        let lens = Lens::from_physical(50.0, Sensor::full_frame(), 8.0, Focus::Distance(5.0));
+        assert_approx_eq!(lens.exposure(), 1.0);
+
+        let lens = lens.with_exposure(Exposure::new(1.0 / 125.0, 100.0));
+        assert_approx_eq!(lens.exposure(), 8192.0 / 8000.0);
+        let camera = Camera::new(orientation(), lens, 1.0);
+        assert_approx_eq!(camera.exposure(), 8192.0 / 8000.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn lens_exposure_without_f_stop() {
+        let _ = Lens::new(90.0, 0.0, Focus::Distance(1.0))
+            .with_exposure(Exposure::new(1.0 / 125.0, 100.0));
+    }
+
+    // Projections
+
+    #[test]
+    fn camera_perspective_centre() {
+        let camera = Camera::new(
+            orientation(),
+            Lens::new(90.0, 0.0, Focus::Distance(1.0)),
+            1.0,
+        );
+        let given = camera.get_ray(0.5, 0.5).unwrap().direction.unit();
+        let expected = Vector::new(0.0, 0.0, -1.0);
+        vec_approx_equal(given, expected);
+    }
+
+    #[test]
+    fn camera_autofocus() {
+        let mut camera = Camera::new(
+            orientation(),
+            Lens::new(90.0, 0.0, Focus::Auto { s: 0.75, t: 0.5 }),
+            1.0,
+        );
+        let world = Object::new_sphere(
+            Vector::new(2.0, 0.0, -4.0),
+            1.0,
+            Material::new_lambertian(Vector::new(0.5, 0.5, 0.5)),
+        );
+        camera.autofocus(&world);
+        match camera.projection {
+            Projection::Perspective { focus_dist, .. } => {
+                assert_approx_eq!(focus_dist, 4.0 - 1.25_f64.sqrt().recip())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn camera_orthographic_parallel() {
+        let camera = Camera::new_orthographic(orientation(), 2.0, 2.0);
+        let given = camera.get_ray(1.0, 1.0).unwrap();
+        vec_approx_equal(given.origin, Vector::new(2.0, 1.0, 0.0));
+        vec_approx_equal(given.direction, Vector::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn camera_fisheye_edge() {
+        let camera = Camera::new_fisheye(orientation(), 180.0, Mapping::Equidistant, 1.0);
+        let given = camera.get_ray(1.0, 0.5).unwrap().direction.unit();
+        let expected = Vector::new(1.0, 0.0, 0.0);
+        vec_approx_equal(given, expected);
+
+        let camera = Camera::new_fisheye(orientation(), 180.0, Mapping::Equisolid, 1.0);
+        let given = camera.get_ray(0.5, 0.0).unwrap().direction.unit();
+        let expected = Vector::new(0.0, -1.0, 0.0);
+        vec_approx_equal(given, expected);
+    }
+
+    #[test]
+    fn camera_fisheye_outside_circle() {
+        let camera = Camera::new_fisheye(orientation(), 180.0, Mapping::Equidistant, 1.0);
+        assert!(camera.get_ray(1.0, 1.0).is_none());
+    }
+
+    #[test]
+    fn camera_equirectangular() {
+        let camera = Camera::new_equirectangular(orientation());
+        let given = camera.get_ray(0.5, 0.5).unwrap().direction;
+        vec_approx_equal(given, Vector::new(0.0, 0.0, -1.0));
+
+        let given = camera.get_ray(0.0, 0.5).unwrap().direction;
+        vec_approx_equal(given, Vector::new(0.0, 0.0, 1.0));
+
+        let given = camera.get_ray(0.75, 0.5).unwrap().direction;
+        vec_approx_equal(given, Vector::new(1.0, 0.0, 0.0));
+
+        let given = camera.get_ray(0.5, 1.0).unwrap().direction;
+        vec_approx_equal(given, Vector::new(0.0, 1.0, 0.0));
+    }
 }
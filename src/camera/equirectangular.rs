@@ -0,0 +1,13 @@
+use std::f64::consts::PI;
+
+use crate::camera::Frame;
+use crate::ray::Ray;
+
+pub fn get_ray(frame: &Frame, s: f64, t: f64, time: f64) -> Ray {
+    let longitude = (s - 0.5) * 2.0 * PI;
+    let latitude = (t - 0.5) * PI;
+
+    let direction = latitude.cos() * longitude.sin() * frame.u + latitude.sin() * frame.v
+        - latitude.cos() * longitude.cos() * frame.w;
+    Ray::new(frame.origin, direction, time)
+}
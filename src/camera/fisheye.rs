@@ -0,0 +1,31 @@
+use crate::camera::{Frame, Mapping};
+use crate::ray::Ray;
+
+pub fn get_ray(
+    frame: &Frame,
+    half_fov: f64,
+    aspect: f64,
+    mapping: Mapping,
+    s: f64,
+    t: f64,
+    time: f64,
+) -> Option<Ray> {
+    // Position relative to the centre of the image circle, which has unit radius
+    let x = (2.0 * s - 1.0) * aspect;
+    let y = 2.0 * t - 1.0;
+    let r = (x * x + y * y).sqrt();
+    if r > 1.0 {
+        return None;
+    }
+
+    // Angle from the optical axis
+    let theta = match mapping {
+        Mapping::Equidistant => r * half_fov,
+        Mapping::Equisolid => 2.0 * (r * (half_fov / 2.0).sin()).asin(),
+    };
+    let phi = y.atan2(x);
+
+    let direction = theta.sin() * phi.cos() * frame.u + theta.sin() * phi.sin() * frame.v
+        - theta.cos() * frame.w;
+    Some(Ray::new(frame.origin, direction, time))
+}
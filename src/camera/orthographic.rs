@@ -0,0 +1,9 @@
+use crate::camera::Frame;
+use crate::ray::Ray;
+
+pub fn get_ray(frame: &Frame, half_width: f64, half_height: f64, s: f64, t: f64, time: f64) -> Ray {
+    let origin = frame.origin
+        + (2.0 * s - 1.0) * half_width * frame.u
+        + (2.0 * t - 1.0) * half_height * frame.v;
+    Ray::new(origin, -frame.w, time)
+}
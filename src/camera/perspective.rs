@@ -0,0 +1,26 @@
+use crate::camera::Frame;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+#[allow(clippy::too_many_arguments)]
+pub fn get_ray(
+    frame: &Frame,
+    half_width: f64,
+    half_height: f64,
+    lens_radius: f64,
+    focus_dist: f64,
+    s: f64,
+    t: f64,
+    time: f64,
+) -> Ray {
+    // Generate a random position on a disk around the origin
+    let rd = lens_radius * Vector::random();
+    let offset = frame.u * rd.x + frame.v * rd.y;
+
+    // Point on the plane of focus
+    let target = focus_dist
+        * ((2.0 * s - 1.0) * half_width * frame.u + (2.0 * t - 1.0) * half_height * frame.v
+            - frame.w);
+
+    Ray::new(frame.origin + offset, target - offset, time)
+}
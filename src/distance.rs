@@ -0,0 +1,137 @@
+use crate::vector::Vector;
+
+// Signed distance functions for implicit surfaces, negative inside the
+// surface and positive outside, after Inigo Quilez's collection.
+//
+// Each shape is centred on the origin. Functions marked as estimates only
+// bound the true distance, which still suffices for sphere tracing.
+
+pub fn sphere(p: Vector, radius: f64) -> f64 {
+    p.length() - radius
+}
+
+/// Box with edges rounded off by `radius`, which is included in
+/// `half_extents`.
+pub fn rounded_box(p: Vector, half_extents: Vector, radius: f64) -> f64 {
+    let q = Vector::new(p.x.abs(), p.y.abs(), p.z.abs()) - half_extents
+        + Vector::new(radius, radius, radius);
+    let outside = Vector::new(q.x.max(0.0), q.y.max(0.0), q.z.max(0.0));
+    outside.length() + q.x.max(q.y).max(q.z).min(0.0) - radius
+}
+
+/// Torus lying in the xz plane.
+pub fn torus(p: Vector, major_radius: f64, minor_radius: f64) -> f64 {
+    let ring = (p.x * p.x + p.z * p.z).sqrt() - major_radius;
+    (ring * ring + p.y * p.y).sqrt() - minor_radius
+}
+
+pub fn union(d1: f64, d2: f64) -> f64 {
+    d1.min(d2)
+}
+
+pub fn intersection(d1: f64, d2: f64) -> f64 {
+    d1.max(d2)
+}
+
+pub fn difference(d1: f64, d2: f64) -> f64 {
+    d1.max(-d2)
+}
+
+/// Union which blends the two surfaces together over a distance of about
+/// `k`. This is an estimate.
+pub fn smooth_union(d1: f64, d2: f64, k: f64) -> f64 {
+    let h = (0.5 + 0.5 * (d2 - d1) / k).clamp(0.0, 1.0);
+    d2 + (d1 - d2) * h - k * h * (1.0 - h)
+}
+
+/// Mandelbulb fractal of the given `power`, which fits within a sphere of
+/// radius 1.2 for the classic power of 8. This is an estimate.
+pub fn mandelbulb(p: Vector, power: f64, iterations: u32) -> f64 {
+    let mut z = p;
+    let mut dr = 1.0;
+    let mut r = 0.0;
+
+    for _ in 0..iterations {
+        r = z.length();
+        if r > 2.0 {
+            break;
+        }
+
+        // An orbit through the origin repeats, so lies within the fractal,
+        // and the origin has no direction to raise to the power
+        if r == 0.0 {
+            return 0.0;
+        }
+
+        // Raise z to the power in spherical coordinates
+        let theta = (z.z / r).acos() * power;
+        let phi = z.y.atan2(z.x) * power;
+        dr = r.powf(power - 1.0) * power * dr + 1.0;
+        let zr = r.powf(power);
+        z =
+            zr * Vector::new(
+                theta.sin() * phi.cos(),
+                phi.sin() * theta.sin(),
+                theta.cos(),
+            ) + p;
+    }
+
+    0.5 * r.ln() * r / dr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    #[test]
+    fn distance_sphere() {
+        assert_approx_eq!(sphere(Vector::new(0.0, 3.0, 0.0), 1.0), 2.0);
+        assert_approx_eq!(sphere(Vector::new(0.0, 0.0, 0.0), 1.0), -1.0);
+    }
+
+    #[test]
+    fn distance_rounded_box() {
+        let half_extents = Vector::new(1.0, 2.0, 3.0);
+        assert_approx_eq!(
+            rounded_box(Vector::new(2.0, 0.0, 0.0), half_extents, 0.0),
+            1.0
+        );
+        assert_approx_eq!(
+            rounded_box(Vector::new(0.0, 0.0, 0.0), half_extents, 0.0),
+            -1.0
+        );
+
+        // The corner is rounded away
+        let corner = Vector::new(1.0, 2.0, 3.0);
+        assert_approx_eq!(
+            rounded_box(corner, half_extents, 0.5),
+            0.5 * 3.0_f64.sqrt() - 0.5
+        );
+    }
+
+    #[test]
+    fn distance_torus() {
+        assert_approx_eq!(torus(Vector::new(2.0, 1.0, 0.0), 2.0, 0.5), 0.5);
+        assert_approx_eq!(torus(Vector::new(0.0, 0.0, 0.0), 2.0, 0.5), 1.5);
+    }
+
+    #[test]
+    fn distance_combinations() {
+        assert_approx_eq!(union(1.0, -2.0), -2.0);
+        assert_approx_eq!(intersection(1.0, -2.0), 1.0);
+        assert_approx_eq!(difference(1.0, -2.0), 2.0);
+
+        // Far apart, smoothing makes no difference
+        assert_approx_eq!(smooth_union(1.0, 5.0, 0.5), 1.0);
+        // Where the shapes meet, smoothing pulls the surface outwards
+        assert!(smooth_union(0.1, 0.1, 0.5) < 0.1);
+    }
+
+    #[test]
+    fn distance_mandelbulb() {
+        assert!(mandelbulb(Vector::new(0.3, 0.2, 0.1), 8.0, 10) < 0.0);
+        assert!(mandelbulb(Vector::new(2.0, 0.0, 0.0), 8.0, 10) > 0.5);
+        assert_approx_eq!(mandelbulb(Vector::new(0.0, 0.0, 0.0), 8.0, 10), 0.0);
+    }
+}
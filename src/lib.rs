@@ -0,0 +1,17 @@
+pub mod aabb;
+pub mod camera;
+pub mod distance;
+pub mod materials;
+pub mod matrix;
+pub mod media;
+pub mod mesh;
+pub mod objects;
+pub mod polynomial;
+pub mod ray;
+pub mod render;
+pub mod scene;
+pub mod spectrum;
+pub mod subdivision;
+pub mod texture;
+pub mod trajectory;
+pub mod vector;
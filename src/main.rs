@@ -1,20 +1,11 @@
-use image;
 use rand::Rng;
 
-mod camera;
-mod materials;
-mod objects;
-mod ray;
-mod render;
-mod scene;
-mod vector;
-
-use camera::{Camera, Lens, Orientation};
-
-use materials::Material;
-use objects::Object;
-use scene::Scene;
-use vector::Vector;
+use daphnis::camera::{Camera, Focus, Lens, Orientation, Shutter};
+use daphnis::materials::Material;
+use daphnis::objects::Object;
+use daphnis::render;
+use daphnis::scene::Scene;
+use daphnis::vector::Vector;
 
 fn main() {
     // Initialise image size and quality
@@ -28,12 +19,12 @@ fn main() {
         Vector::new(0.0, 0.0, 0.0),
         Vector::new(0.0, 1.0, 0.0),
     );
-    let lens = Lens::new(20.0, 0.1, 10.0);
+    let lens = Lens::new(20.0, 0.1, Focus::auto());
     let aspect = f64::from(nx) / f64::from(ny);
     let camera = Camera::new(orientation, lens, aspect);
 
     // Initialise world
-    let world = random_world();
+    let world = random_world(camera.shutter());
 
     // Initialise scene
     let scene = Scene::new(nx, ny, ns, camera, world);
@@ -45,13 +36,13 @@ fn main() {
     image::save_buffer("output.png", &data, nx, ny, image::RGB(8)).unwrap();
 }
 
-fn random_world() -> Object {
+fn random_world(shutter: Shutter) -> Object {
     let mut object_list = vec![];
 
     // Floor
-    object_list.push(Object::new_sphere(
-        Vector::new(0.0, -1000.0, 0.0),
-        1000.0,
+    object_list.push(Object::new_plane(
+        Vector::new(0.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
         Material::new_lambertian(Vector::new(0.5, 0.5, 0.5)),
     ));
 
@@ -117,5 +108,5 @@ fn random_world() -> Object {
         Material::new_metal(Vector::new(0.7, 0.6, 0.5), 0.0),
     ));
 
-    Object::Multiple(object_list)
+    Object::new_bvh(object_list, shutter)
 }
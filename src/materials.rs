@@ -1,10 +1,23 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::media::{Dispersion, Media, Medium};
 use crate::objects::HitRecord;
 use crate::ray::Ray;
+use crate::spectrum;
+use crate::texture::Texture;
 use crate::vector::Vector;
 
+mod conductor;
 mod dielectric;
 mod lambertian;
+mod layered;
 mod metal;
+mod microfacet;
+mod oren_nayar;
+mod principled;
+mod rough_dielectric;
 
 #[derive(Copy, Clone, Debug)]
 pub struct Scatter {
@@ -16,33 +29,864 @@ impl Scatter {
     pub fn new(attenuation: Vector, ray: Ray) -> Scatter {
         Scatter { attenuation, ray }
     }
+
+    /// All of the light absorbed at the hit, ending the path.
+    pub fn absorbed() -> Scatter {
+        let zero = Vector::new(0.0, 0.0, 0.0);
+        Scatter::new(zero, Ray::new(zero, zero, 0.0))
+    }
+
+    /// Whether no light is carried on, so that the ray need not be traced.
+    pub fn is_absorbed(&self) -> bool {
+        self.attenuation.x == 0.0 && self.attenuation.y == 0.0 && self.attenuation.z == 0.0
+    }
 }
 
+/// Fraction of light reflected by a metal, depending on the angle of
+/// incidence.
 #[derive(Copy, Clone, Debug)]
+pub enum Fresnel {
+    /// Schlick's approximation, given the `reflectance` seen head on.
+    Schlick { reflectance: Vector },
+    /// Exact reflectance of a complex index of refraction `eta + i k` in
+    /// each channel.
+    Complex { eta: Vector, k: Vector },
+}
+
+/// Parameters of the principled material, after Burley, "Physically-Based
+/// Shading at Disney" (2012), as exported by glTF and USD assets. Weights
+/// range from 0 to 1.
+#[derive(Copy, Clone, Debug)]
+pub struct Principled {
+    /// Diffuse colour, or reflectance head on for metals.
+    pub base_colour: Vector,
+    /// Blend from dielectric to metal.
+    pub metallic: f64,
+    /// Perceptual roughness of the specular, metal and transmission layers.
+    pub roughness: f64,
+    /// Reflectance head on of dielectrics, scaled so that the default of 0.5
+    /// gives the 4% of an index of refraction of 1.5.
+    pub specular: f64,
+    /// Weight of the soft retroreflection of cloth at grazing angles.
+    pub sheen: f64,
+    /// Blend of the sheen from white towards the base colour.
+    pub sheen_tint: f64,
+    /// Weight of a clear lacquer layer on top.
+    pub clearcoat: f64,
+    pub clearcoat_roughness: f64,
+    /// Blend from an opaque to a glass-like dielectric.
+    pub transmission: f64,
+    // Identity of the medium entered by transmitted light
+    medium_id: u64,
+}
+
+impl Principled {
+    /// Rough dielectric of the given base colour, which the other parameters
+    /// can then be set from.
+    pub fn new(base_colour: Vector) -> Principled {
+        Principled {
+            base_colour,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+            transmission: 0.0,
+            medium_id: Medium::new_id(),
+        }
+    }
+
+    // Medium entered by transmitted light, of the index of refraction of
+    // the dielectric layers.
+    fn medium(&self) -> Medium {
+        Medium {
+            id: self.medium_id,
+            ref_idx: self.ref_idx(),
+            absorption: Vector::new(0.0, 0.0, 0.0),
+            priority: 0,
+            dispersion: None,
+        }
+    }
+
+    // Index of refraction of the dielectric layers, from their reflectance
+    // head on.
+    fn ref_idx(&self) -> f64 {
+        let r = (0.08 * self.specular).clamp(0.0, 0.99).sqrt();
+        (1.0 + r) / (1.0 - r)
+    }
+
+    // Colour of the sheen, tinted towards the hue of the base colour but no
+    // brighter than white in any channel.
+    fn sheen_colour(&self) -> Vector {
+        let white = Vector::new(1.0, 1.0, 1.0);
+        let c = self.base_colour;
+        let luminance = 0.3 * c.x + 0.6 * c.y + 0.1 * c.z;
+        let tint = if luminance > 0.0 {
+            c / luminance
+        } else {
+            white
+        };
+        let colour = white + self.sheen_tint * (tint - white);
+        Vector::new(colour.x.min(1.0), colour.y.min(1.0), colour.z.min(1.0))
+    }
+}
+
+/// Thin dielectric coat over another material, such as varnish or the
+/// clearcoat of car paint.
+#[derive(Copy, Clone, Debug)]
+pub struct Coating {
+    pub ref_idx: f64,
+    pub roughness: f64,
+    /// Colour of light crossing a coat of unit thickness straight through.
+    pub tint: Vector,
+    pub thickness: f64,
+}
+
+impl Coating {
+    pub fn new(ref_idx: f64, roughness: f64, tint: Vector, thickness: f64) -> Coating {
+        Coating {
+            ref_idx,
+            roughness,
+            tint,
+            thickness,
+        }
+    }
+
+    // Fraction of light left after crossing the coat in a direction at
+    // `cosine` to its normal.
+    fn transmittance(&self, cosine: f64) -> Vector {
+        let distance = self.thickness / cosine.abs().max(1e-6);
+        Vector::new(
+            self.tint.x.powf(distance),
+            self.tint.y.powf(distance),
+            self.tint.z.powf(distance),
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
 pub enum Material {
-    Dielectric { ref_idx: f64 },
-    Lambertian { albedo: Vector },
-    Metal { albedo: Vector, fuzz: f64 },
+    Conductor {
+        fresnel: Fresnel,
+        roughness: f64,
+    },
+    Dielectric {
+        medium: Medium,
+    },
+    Lambertian {
+        albedo: Vector,
+    },
+    Layered {
+        coating: Coating,
+        base: Arc<Material>,
+    },
+    Metal {
+        albedo: Vector,
+        fuzz: f64,
+    },
+    Mix {
+        first: Arc<Material>,
+        second: Arc<Material>,
+        weight: Texture,
+    },
+    OrenNayar {
+        albedo: Vector,
+        sigma: f64,
+    },
+    Principled(Principled),
+    RoughDielectric {
+        medium: Medium,
+        roughness: f64,
+    },
 }
 
 impl Material {
+    /// Metal whose microscopic roughness follows the GGX distribution, from
+    /// a mirror at a `roughness` of 0 to fully rough at 1. The
+    /// `reflectance` is the colour of the metal seen head on.
+    pub fn new_conductor(reflectance: Vector, roughness: f64) -> Material {
+        Material::Conductor {
+            fresnel: Fresnel::Schlick { reflectance },
+            roughness,
+        }
+    }
+
+    /// Rough metal given by its complex index of refraction `eta + i k` in
+    /// each channel, as measured for real metals.
+    pub fn new_complex_conductor(eta: Vector, k: Vector, roughness: f64) -> Material {
+        Material::Conductor {
+            fresnel: Fresnel::Complex { eta, k },
+            roughness,
+        }
+    }
+
+    /// Rough metal whose complex index of refraction is taken from the
+    /// built-in table by `name`, ignoring case: gold, silver, copper,
+    /// aluminium (or aluminum), chrome (or chromium) and iron.
+    pub fn new_named_conductor(name: &str, roughness: f64) -> Option<Material> {
+        conductor::preset(name).map(|(eta, k)| Material::new_complex_conductor(eta, k, roughness))
+    }
+
+    /// Smooth glass or other dielectric. Each call gives a medium of its
+    /// own, which objects sharing the material also share.
     pub fn new_dielectric(ref_idx: f64) -> Material {
-        Material::Dielectric { ref_idx }
+        Material::Dielectric {
+            medium: Medium::new(ref_idx, Vector::new(0.0, 0.0, 0.0), 0),
+        }
     }
 
     pub fn new_lambertian(albedo: Vector) -> Material {
         Material::Lambertian { albedo }
     }
 
+    /// The `base` material under a thin dielectric `coating`, which
+    /// reflects part of the light before it reaches the base and traps part
+    /// of the light scattered back up by the base.
+    pub fn new_layered(coating: Coating, base: Material) -> Material {
+        Material::Layered {
+            coating,
+            base: Arc::new(base),
+        }
+    }
+
     pub fn new_metal(albedo: Vector, fuzz: f64) -> Material {
         Material::Metal { albedo, fuzz }
     }
 
-    pub fn scatter(&self, r_in: &Ray, hit: &HitRecord) -> Scatter {
+    /// Rough diffuse material, such as clay, concrete or fabric, whose
+    /// microscopic facets have slopes varying by `sigma` radians. A `sigma`
+    /// of 0 is Lambertian.
+    pub fn new_oren_nayar(albedo: Vector, sigma: f64) -> Material {
+        Material::OrenNayar { albedo, sigma }
+    }
+
+    /// Blend of two materials, scattering off the `second` with the
+    /// probability given by the `weight` at the hit and otherwise off the
+    /// `first`. As the choice is made afresh at every hit, a path could
+    /// enter a medium through one material and never leave it through the
+    /// other, so transmitting dielectrics cannot be mixed.
+    pub fn new_mix(first: Material, second: Material, weight: Texture) -> Material {
+        assert!(
+            !first.transmits() && !second.transmits(),
+            "Mix cannot blend transmitting dielectrics!"
+        );
+        Material::Mix {
+            first: Arc::new(first),
+            second: Arc::new(second),
+            weight,
+        }
+    }
+
+    /// Single material covering diffuse, metallic, glossy, cloth, lacquered
+    /// and glass surfaces through its `principled` parameters.
+    pub fn new_principled(principled: Principled) -> Material {
+        Material::Principled(principled)
+    }
+
+    /// Glass or other dielectric whose surface is roughened following the
+    /// GGX distribution, such as frosted glass. A `roughness` of 0 gives a
+    /// smooth interface, like `new_dielectric`.
+    pub fn new_rough_dielectric(ref_idx: f64, roughness: f64) -> Material {
+        Material::RoughDielectric {
+            medium: Medium::new(ref_idx, Vector::new(0.0, 0.0, 0.0), 0),
+            roughness,
+        }
+    }
+
+    /// Same dielectric, absorbing light inside it by the `absorption`
+    /// coefficient of each channel per unit distance travelled. Light keeps
+    /// a `colour` after travelling a `distance` for an absorption of
+    /// `-ln(colour) / distance`. Other materials are unchanged.
+    pub fn with_absorption(mut self, absorption: Vector) -> Material {
+        if let Some(medium) = self.medium_mut() {
+            medium.absorption = absorption;
+        }
+        self
+    }
+
+    /// Same dielectric, filling any overlap with other dielectrics of lower
+    /// `priority`, such as a glass holding a liquid which overlaps its walls
+    /// slightly. Dielectrics are of priority 0 by default. Other materials
+    /// are unchanged.
+    pub fn with_priority(mut self, priority: u32) -> Material {
+        if let Some(medium) = self.medium_mut() {
+            medium.priority = priority;
+        }
+        self
+    }
+
+    /// Same dielectric, with an index of refraction varying with wavelength
+    /// in spectral rendering. Otherwise the index at the sodium D line of
+    /// 589.3 nm is used. Other materials are unchanged.
+    pub fn with_dispersion(mut self, dispersion: Dispersion) -> Material {
+        if let Some(medium) = self.medium_mut() {
+            medium.ref_idx = dispersion.ref_idx(589.3);
+            medium.dispersion = Some(dispersion);
+        }
+        self
+    }
+
+    // Whether light can be refracted into a medium inside the surface.
+    fn transmits(&self) -> bool {
+        match self {
+            Material::Dielectric { .. } | Material::RoughDielectric { .. } => true,
+            Material::Layered { base, .. } => base.transmits(),
+            Material::Mix { first, second, .. } => first.transmits() || second.transmits(),
+            Material::Principled(principled) => principled.transmission > 0.0,
+            _ => false,
+        }
+    }
+
+    fn medium_mut(&mut self) -> Option<&mut Medium> {
+        match self {
+            Material::Dielectric { medium } | Material::RoughDielectric { medium, .. } => {
+                Some(medium)
+            }
+            _ => None,
+        }
+    }
+
+    /// Same material with its albedo multiplied by `colour`, or `None` for
+    /// dielectrics and metals given by their index of refraction, which
+    /// have no albedo to tint.
+    pub fn tint(&self, colour: Vector) -> Option<Material> {
+        match *self {
+            Material::Conductor {
+                fresnel: Fresnel::Schlick { reflectance },
+                roughness,
+            } => Some(Material::new_conductor(reflectance * colour, roughness)),
+            Material::Conductor { .. }
+            | Material::Dielectric { .. }
+            | Material::RoughDielectric { .. } => None,
+            Material::Lambertian { albedo } => Some(Material::Lambertian {
+                albedo: albedo * colour,
+            }),
+            Material::Layered { coating, ref base } => {
+                Some(Material::new_layered(coating, base.tint(colour)?))
+            }
+            Material::Metal { albedo, fuzz } => Some(Material::Metal {
+                albedo: albedo * colour,
+                fuzz,
+            }),
+            Material::Mix {
+                ref first,
+                ref second,
+                ref weight,
+            } => Some(Material::new_mix(
+                first.tint(colour)?,
+                second.tint(colour)?,
+                weight.clone(),
+            )),
+            Material::OrenNayar { albedo, sigma } => Some(Material::OrenNayar {
+                albedo: albedo * colour,
+                sigma,
+            }),
+            Material::Principled(principled) => Some(Material::Principled(Principled {
+                base_colour: principled.base_colour * colour,
+                ..principled
+            })),
+        }
+    }
+
+    /// Scatter the ray arriving at the hit, given the `media` the path is
+    /// inside, which are updated if the ray is transmitted through the
+    /// surface. Light is first absorbed by the current medium along the ray.
+    ///
+    /// Paths of a single `wavelength` are attenuated equally in every
+    /// channel, by the value of the spectrum of the RGB attenuation.
+    pub fn scatter(
+        &self,
+        r_in: &Ray,
+        hit: &HitRecord,
+        media: &mut Media,
+        wavelength: Option<f64>,
+    ) -> Scatter {
+        let transmittance = media.transmittance(hit.t * r_in.direction.length());
+        let mut scatter = self.scatter_surface(r_in, hit, media, wavelength);
+        scatter.attenuation *= transmittance;
+        if let Some(wavelength) = wavelength {
+            let value = spectrum::uplift(scatter.attenuation, wavelength);
+            scatter.attenuation = Vector::new(value, value, value);
+        }
+        scatter
+    }
+
+    // Scatter by the surface alone, in RGB.
+    fn scatter_surface(
+        &self,
+        r_in: &Ray,
+        hit: &HitRecord,
+        media: &mut Media,
+        wavelength: Option<f64>,
+    ) -> Scatter {
         match *self {
-            Material::Dielectric { ref_idx } => dielectric::scatter(ref_idx, r_in, hit),
+            Material::Conductor { fresnel, roughness } => {
+                conductor::scatter(fresnel, roughness, r_in, hit)
+            }
+            Material::Dielectric { medium } => {
+                dielectric::scatter(&medium, r_in, hit, media, wavelength)
+            }
             Material::Lambertian { albedo } => lambertian::scatter(albedo, r_in, hit),
+            Material::Layered {
+                ref coating,
+                ref base,
+            } => layered::scatter(coating, base, r_in, hit, media, wavelength),
             Material::Metal { albedo, fuzz } => metal::scatter(albedo, fuzz, r_in, hit),
+            Material::Mix {
+                ref first,
+                ref second,
+                ref weight,
+            } => {
+                let mut rng = rand::thread_rng();
+                if rng.gen::<f64>() < weight.value(hit) {
+                    second.scatter_surface(r_in, hit, media, wavelength)
+                } else {
+                    first.scatter_surface(r_in, hit, media, wavelength)
+                }
+            }
+            Material::OrenNayar { albedo, sigma } => oren_nayar::scatter(albedo, sigma, r_in, hit),
+            Material::Principled(principled) => {
+                principled::scatter(&principled, r_in, hit, media, wavelength)
+            }
+            Material::RoughDielectric { medium, roughness } => {
+                rough_dielectric::scatter(&medium, roughness, r_in, hit, media, wavelength)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    // Utility function to check the approximate equality of two vectors.
+    //
+    // Direct equality comparison between two vectors is unavailable due to
+    // the underlying float representation.
+    fn vec_approx_equal(v1: Vector, v2: Vector) {
+        assert_approx_eq!(v1.x, v2.x);
+        assert_approx_eq!(v1.y, v2.y);
+        assert_approx_eq!(v1.z, v2.z);
+    }
+
+    // Hit at the origin of a surface facing up, by a ray arriving at
+    // `angle` degrees from the normal.
+    fn arrive(material: &Material, angle: f64) -> (Ray, HitRecord<'_>) {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let origin = Vector::new(-sin, cos, 0.0);
+        let r = Ray::new(origin, -origin, 0.0);
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let hit = HitRecord::new(1.0, Vector::new(0.0, 0.0, 0.0), normal, 0.0, 0.0, material);
+        (r, hit)
+    }
+
+    // Mean attenuation over many samples, which for a white material is the
+    // fraction of energy reflected.
+    fn albedo(material: Material, angle: f64) -> Vector {
+        let (r, hit) = arrive(&material, angle);
+        let samples = 10_000;
+        let mut total = Vector::new(0.0, 0.0, 0.0);
+        for _ in 0..samples {
+            total += material
+                .scatter(&r, &hit, &mut Media::new(), None)
+                .attenuation;
+        }
+        total / f64::from(samples)
+    }
+
+    #[test]
+    fn material_microfacet_smooth() {
+        assert_approx_eq!(microfacet::alpha(0.5), 0.25);
+
+        // The distribution of a smooth surface is narrow enough that its
+        // long tail still keeps microfacets within a milliradian of the
+        // surface normal
+        let alpha = microfacet::alpha(0.0);
+        let wo = Vector::new(1.0, 0.0, 1.0).unit();
+        let tilted = (0..10_000)
+            .filter(|_| microfacet::sample_visible_normal(&wo, alpha).z < 1e-3f64.cos())
+            .count();
+        assert!(tilted < 10, "{}", tilted);
+    }
+
+    #[test]
+    fn material_conductor_smooth() {
+        let material = Material::new_conductor(Vector::new(0.9, 0.6, 0.3), 0.0);
+        let (r, hit) = arrive(&material, 45.0);
+        let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+        let given = scatter.ray.direction.unit();
+        let expected = Vector::new(1.0, 1.0, 0.0).unit();
+        assert!((given - expected).length() < 1e-3);
+
+        // Head on, the reflectance is the given colour
+        let (r, hit) = arrive(&material, 0.0);
+        let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+        vec_approx_equal(scatter.attenuation, Vector::new(0.9, 0.6, 0.3));
+    }
+
+    #[test]
+    fn material_conductor_energy() {
+        let white = Vector::new(1.0, 1.0, 1.0);
+        for &angle in [0.0, 45.0, 80.0].iter() {
+            // Nearly smooth metal loses almost nothing
+            let given = albedo(Material::new_conductor(white, 0.1), angle);
+            assert!(given.x > 0.99 && given.x <= 1.0, "{:?}", given);
+
+            // Rough metal loses energy to shadowing, but never gains it
+            let given = albedo(Material::new_conductor(white, 1.0), angle);
+            assert!(given.x > 0.0 && given.x < 1.0, "{:?}", given);
+        }
+    }
+
+    #[test]
+    fn material_complex_conductor() {
+        // Head on, the reflectance of a metal follows from its complex index
+        let (eta, k) = (Vector::new(0.2, 1.0, 1.5), Vector::new(4.0, 2.5, 1.5));
+        let material = Material::new_complex_conductor(eta, k, 0.0);
+        let (r, hit) = arrive(&material, 0.0);
+        let given = material
+            .scatter(&r, &hit, &mut Media::new(), None)
+            .attenuation;
+        let expected =
+            |eta: f64, k: f64| ((eta - 1.0).powi(2) + k * k) / ((eta + 1.0).powi(2) + k * k);
+        assert_approx_eq!(given.x, expected(eta.x, k.x), 1e-4);
+        assert_approx_eq!(given.y, expected(eta.y, k.y), 1e-4);
+        assert_approx_eq!(given.z, expected(eta.z, k.z), 1e-4);
+
+        // At grazing angles, every metal becomes a perfect mirror
+        assert_approx_eq!(microfacet::conductor(0.0, eta.z, k.z), 1.0);
+    }
+
+    #[test]
+    fn material_named_conductor() {
+        let gold = Material::new_named_conductor("Gold", 0.0).unwrap();
+        let (r, hit) = arrive(&gold, 0.0);
+        let given = gold.scatter(&r, &hit, &mut Media::new(), None).attenuation;
+        assert!(given.x > 0.9 && given.z < 0.4, "{:?}", given);
+
+        assert!(Material::new_named_conductor("aluminum", 0.0).is_some());
+        assert!(Material::new_named_conductor("unobtainium", 0.0).is_none());
+    }
+
+    #[test]
+    fn material_rough_dielectric_smooth() {
+        // Nearly all light passes straight through a smooth interface head on
+        let material = Material::new_rough_dielectric(1.5, 0.0);
+        let (r, hit) = arrive(&material, 0.0);
+        let straight = (0..1000)
+            .filter(|_| {
+                let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+                (scatter.ray.direction.unit() - r.direction).length() < 1e-3
+            })
+            .count();
+        assert!(straight > 900 && straight < 1000, "{}", straight);
+
+        // Leaving glass beyond the critical angle, all light is reflected
+        // back inside
+        let (sin, cos) = 60f64.to_radians().sin_cos();
+        let r = Ray::new(
+            Vector::new(-sin, -cos, 0.0),
+            Vector::new(sin, cos, 0.0),
+            0.0,
+        );
+        for _ in 0..100 {
+            let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+            assert!(scatter.ray.direction.y < 0.0, "{:?}", scatter.ray.direction);
+        }
+    }
+
+    #[test]
+    fn material_rough_dielectric_energy() {
+        // Nearly smooth glass loses almost nothing, while rough glass loses
+        // energy to shadowing but never gains it
+        for &angle in [0.0, 45.0, 80.0].iter() {
+            let given = albedo(Material::new_rough_dielectric(1.5, 0.1), angle);
+            assert!(given.x > 0.98 && given.x <= 1.0, "{:?}", given);
+
+            let given = albedo(Material::new_rough_dielectric(1.5, 1.0), angle);
+            assert!(given.x > 0.0 && given.x < 1.0, "{:?}", given);
+        }
+    }
+
+    #[test]
+    fn material_dielectric_absorption() {
+        let absorption = Vector::new(0.0, 0.5, 2.0);
+        let material = Material::new_dielectric(1.5).with_absorption(absorption);
+
+        // Light arriving from outside has not been absorbed yet
+        let (r, hit) = arrive(&material, 0.0);
+        let given = material
+            .scatter(&r, &hit, &mut Media::new(), None)
+            .attenuation;
+        vec_approx_equal(given, Vector::new(1.0, 1.0, 1.0));
+
+        // Light leaving has been absorbed along its path inside, twice as
+        // much over twice the distance
+        let mut inside = Media::new();
+        inside.cross(&Medium::new(1.5, absorption, 0), true);
+        let r = Ray::new(Vector::new(0.0, -1.0, 0.0), Vector::new(0.0, 2.0, 0.0), 0.0);
+        let hit = HitRecord::new(0.5, hit.p, hit.normal, 0.0, 0.0, &material);
+        let given = material
+            .scatter(&r, &hit, &mut inside.clone(), None)
+            .attenuation;
+        vec_approx_equal(given, Vector::new(1.0, (-0.5f64).exp(), (-2.0f64).exp()));
+
+        let r = Ray::new(Vector::new(0.0, -2.0, 0.0), Vector::new(0.0, 2.0, 0.0), 0.0);
+        let hit = HitRecord::new(1.0, hit.p, hit.normal, 0.0, 0.0, &material);
+        let given = material
+            .scatter(&r, &hit, &mut inside.clone(), None)
+            .attenuation;
+        vec_approx_equal(given, Vector::new(1.0, (-1.0f64).exp(), (-4.0f64).exp()));
+    }
+
+    #[test]
+    fn material_nested_dielectrics() {
+        // Water overlapping the walls of a glass of higher priority
+        let glass = Material::new_dielectric(1.5).with_priority(1);
+        let water = Material::new_dielectric(1.33);
+        let mut media = Media::new();
+        if let Material::Dielectric { medium } = glass {
+            media.cross(&medium, true);
+        }
+
+        // Inside the glass, the surface of the water is ignored
+        let (r, hit) = arrive(&water, 30.0);
+        let scatter = water.scatter(&r, &hit, &mut media, None);
+        vec_approx_equal(scatter.ray.direction, r.direction);
+        assert_approx_eq!(media.current().unwrap().ref_idx, 1.5);
+
+        // Leaving the glass, the ray enters the water rather than air
+        let hit = HitRecord::new(1.0, hit.p, -hit.normal, 0.0, 0.0, &glass);
+        for _ in 0..100 {
+            let mut media = media.clone();
+            let scatter = glass.scatter(&r, &hit, &mut media, None);
+            if scatter.ray.direction.y < 0.0 {
+                let sin = scatter.ray.direction.unit().x;
+                assert_approx_eq!(sin, 30f64.to_radians().sin() * 1.5 / 1.33);
+                assert_approx_eq!(media.current().unwrap().ref_idx, 1.33);
+            }
+        }
+    }
+
+    #[test]
+    fn material_dielectric_dispersion() {
+        let material =
+            Material::new_dielectric(1.0).with_dispersion(Dispersion::Cauchy { a: 1.5, b: 0.01 });
+        if let Material::Dielectric { medium } = material {
+            assert_approx_eq!(medium.ref_idx, 1.5 + 0.01 / 0.5893f64.powi(2));
+        }
+
+        // Blue light is bent further than red light
+        let (r, hit) = arrive(&material, 45.0);
+        let refracted = |wavelength| loop {
+            let scatter = material.scatter(&r, &hit, &mut Media::new(), Some(wavelength));
+            if scatter.ray.direction.y < 0.0 {
+                return scatter.ray.direction.unit().x;
+            }
+        };
+        let (blue, red) = (refracted(450.0), refracted(650.0));
+        assert!(blue < red, "{} {}", blue, red);
+        assert_approx_eq!(
+            red,
+            45f64.to_radians().sin() / (1.5 + 0.01 / 0.65f64.powi(2))
+        );
+    }
+
+    #[test]
+    fn material_principled_metal() {
+        let mut principled = Principled::new(Vector::new(0.9, 0.6, 0.3));
+        principled.metallic = 1.0;
+        principled.roughness = 0.0;
+        let material = Material::new_principled(principled);
+
+        // A smooth metal is a mirror of its base colour head on
+        let (r, hit) = arrive(&material, 45.0);
+        let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+        let expected = Vector::new(1.0, 1.0, 0.0).unit();
+        assert!((scatter.ray.direction.unit() - expected).length() < 1e-3);
+
+        let (r, hit) = arrive(&material, 0.0);
+        let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+        vec_approx_equal(scatter.attenuation, Vector::new(0.9, 0.6, 0.3));
+    }
+
+    #[test]
+    fn material_principled_energy() {
+        let white = Vector::new(1.0, 1.0, 1.0);
+        for &angle in [0.0, 45.0, 80.0].iter() {
+            let given = albedo(Material::new_principled(Principled::new(white)), angle);
+            assert!(given.x > 0.9 && given.x < 1.01, "{:?}", given);
+        }
+
+        // Sheen replaces the diffuse base rather than adding to it, so no
+        // sample reflects more light than arrives
+        let mut principled = Principled::new(Vector::new(1.0, 0.2, 0.2));
+        principled.sheen = 1.0;
+        principled.sheen_tint = 1.0;
+        let material = Material::new_principled(principled);
+        let (r, hit) = arrive(&material, 80.0);
+        for _ in 0..1000 {
+            let given = material.scatter(&r, &hit, &mut Media::new(), None);
+            let brightest = given.attenuation.x.max(given.attenuation.y);
+            assert!(brightest <= 1.0 + 1e-9, "{:?}", given.attenuation);
+        }
+
+        // A black base only reflects from its clearcoat
+        let mut principled = Principled::new(Vector::new(0.0, 0.0, 0.0));
+        principled.specular = 0.0;
+        let given = albedo(Material::new_principled(principled), 0.0);
+        assert_approx_eq!(given.x, 0.0);
+        principled.clearcoat = 1.0;
+        let given = albedo(Material::new_principled(principled), 0.0);
+        assert_approx_eq!(given.x, 0.04, 0.01);
+    }
+
+    #[test]
+    fn material_principled_transmission() {
+        let mut principled = Principled::new(Vector::new(1.0, 0.5, 0.5));
+        principled.roughness = 0.0;
+        principled.transmission = 1.0;
+        let material = Material::new_principled(principled);
+
+        // Most light enters the object, tinted by the base colour
+        let (r, hit) = arrive(&material, 0.0);
+        let mut transmitted = 0;
+        for _ in 0..1000 {
+            let mut media = Media::new();
+            let scatter = material.scatter(&r, &hit, &mut media, None);
+            if scatter.ray.direction.y < 0.0 {
+                transmitted += 1;
+                assert_approx_eq!(media.current().unwrap().ref_idx, 1.5);
+                assert_approx_eq!(scatter.attenuation.y, 0.5, 0.01);
+            }
+        }
+        assert!(transmitted > 900, "{}", transmitted);
+    }
+
+    #[test]
+    fn material_layered() {
+        let clear = Coating::new(1.5, 0.0, Vector::new(1.0, 1.0, 1.0), 0.0);
+
+        // Over a black base, only the top of the coat reflects
+        let black = Material::new_lambertian(Vector::new(0.0, 0.0, 0.0));
+        let given = albedo(Material::new_layered(clear, black.clone()), 0.0);
+        assert_approx_eq!(given.x, 0.04, 0.01);
+
+        // The rest is absorbed, ending the path rather than tracing on
+        let material = Material::new_layered(clear, black);
+        let (r, hit) = arrive(&material, 0.0);
+        for _ in 0..1000 {
+            let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+            assert!(scatter.is_absorbed() || scatter.ray.direction.y > 0.0);
+        }
+
+        // Over a white base, light trapped in the coat eventually escapes
+        let white = Material::new_lambertian(Vector::new(1.0, 1.0, 1.0));
+        for &angle in [0.0, 45.0, 80.0].iter() {
+            let given = albedo(Material::new_layered(clear, white.clone()), angle);
+            assert!(given.x > 0.98 && given.x < 1.01, "{:?}", given);
+        }
+
+        // A tinted coat colours the base, more so when thicker
+        let tinted = |thickness| Coating::new(1.5, 0.0, Vector::new(1.0, 0.5, 0.5), thickness);
+        let thin = albedo(Material::new_layered(tinted(0.5), white.clone()), 0.0);
+        let thick = albedo(Material::new_layered(tinted(1.0), white.clone()), 0.0);
+        assert!(thin.x > 0.98 && thin.y < 0.5 * thin.x, "{:?}", thin);
+        assert!(thick.y < 0.6 * thin.y, "{:?} {:?}", thick, thin);
+    }
+
+    #[test]
+    fn material_mix() {
+        let black = Material::new_lambertian(Vector::new(0.0, 0.0, 0.0));
+        let white = Material::new_lambertian(Vector::new(1.0, 1.0, 1.0));
+        let mix = |weight| Material::new_mix(black.clone(), white.clone(), weight);
+
+        let given = albedo(mix(Texture::new_constant(0.0)), 0.0);
+        assert_approx_eq!(given.x, 0.0);
+        let given = albedo(mix(Texture::new_constant(1.0)), 0.0);
+        assert_approx_eq!(given.x, 1.0);
+        let given = albedo(mix(Texture::new_constant(0.3)), 0.0);
+        assert_approx_eq!(given.x, 0.3, 0.03);
+
+        // The weight is looked up at the hit, here inside the cells of a
+        // checker board where the cube on the positive side of every axis
+        // is 0
+        let checker = mix(Texture::new_checker(1.0));
+        let given = |p| {
+            let (r, mut hit) = arrive(&checker, 0.0);
+            hit.p = p;
+            checker
+                .scatter(&r, &hit, &mut Media::new(), None)
+                .attenuation
+        };
+        assert_approx_eq!(given(Vector::new(0.5, 0.5, 0.5)).x, 0.0);
+        assert_approx_eq!(given(Vector::new(1.5, 0.5, 0.5)).x, 1.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn material_mix_dielectric() {
+        let glass = Material::new_dielectric(1.5);
+        let white = Material::new_lambertian(Vector::new(1.0, 1.0, 1.0));
+        let _ = Material::new_mix(white, glass, Texture::new_constant(0.5));
+    }
+
+    #[test]
+    fn material_oren_nayar() {
+        // Without roughness, every direction is weighted by the albedo alone
+        let albedo_colour = Vector::new(0.9, 0.6, 0.3);
+        let material = Material::new_oren_nayar(albedo_colour, 0.0);
+        let (r, hit) = arrive(&material, 60.0);
+        for _ in 0..100 {
+            let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+            vec_approx_equal(scatter.attenuation, albedo_colour);
+            assert!(scatter.ray.direction.y > 0.0);
+        }
+
+        // Rough surfaces lose some energy, but never gain it
+        let white = Vector::new(1.0, 1.0, 1.0);
+        for &angle in [0.0, 45.0, 80.0].iter() {
+            let given = albedo(Material::new_oren_nayar(white, 0.5), angle);
+            assert!(given.x > 0.7 && given.x < 1.0, "{:?}", given);
+        }
+
+        // Light is scattered back towards where it came from, which is on
+        // the negative x side
+        let material = Material::new_oren_nayar(white, 0.5);
+        let (r, hit) = arrive(&material, 70.0);
+        let (mut back, mut forward) = (0.0, 0.0);
+        for _ in 0..10_000 {
+            let scatter = material.scatter(&r, &hit, &mut Media::new(), None);
+            if scatter.ray.direction.x < 0.0 {
+                back += scatter.attenuation.x;
+            } else {
+                forward += scatter.attenuation.x;
+            }
+        }
+        assert!(back > 1.1 * forward, "{} {}", back, forward);
+    }
+
+    #[test]
+    fn material_oren_nayar_grazing() {
+        // No single direction is weighted above the albedo, even for very
+        // rough surfaces lit at grazing angles
+        let albedo_colour = Vector::new(0.9, 0.6, 0.3);
+        let material = Material::new_oren_nayar(albedo_colour, 1.5);
+        let (r, hit) = arrive(&material, 89.0);
+        for _ in 0..10_000 {
+            let given = material
+                .scatter(&r, &hit, &mut Media::new(), None)
+                .attenuation;
+            assert!(
+                given.x <= albedo_colour.x + 1e-12
+                    && given.y <= albedo_colour.y + 1e-12
+                    && given.z <= albedo_colour.z + 1e-12,
+                "{:?}",
+                given
+            );
         }
     }
 }
@@ -0,0 +1,90 @@
+use crate::materials::microfacet::{self, Frame};
+use crate::materials::{Fresnel, Scatter};
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Reflect off a rough metal surface by sampling visible GGX microfacets.
+/// The weight of each sample is its Fresnel reflectance scaled by the
+/// shadowing of the reflected ray, so no energy is ever gained.
+pub fn scatter(fresnel: Fresnel, roughness: f64, r_in: &Ray, hit: &HitRecord) -> Scatter {
+    // Shade the side of the surface the ray arrives from
+    let normal = if Vector::dot(&r_in.direction, &hit.normal) > 0.0 {
+        -hit.normal
+    } else {
+        hit.normal
+    };
+    let frame = Frame::new(normal, hit.tangent);
+    let wo = frame.to_local(&-r_in.direction.unit());
+
+    let alpha = microfacet::alpha(roughness);
+    let h = microfacet::sample_visible_normal(&wo, alpha);
+    let wi = microfacet::reflect(&-wo, &h);
+
+    let attenuation = if wi.z > 0.0 {
+        let cosine = Vector::dot(&wi, &h);
+        let reflectance = match fresnel {
+            Fresnel::Schlick { reflectance } => microfacet::schlick(cosine, reflectance),
+            Fresnel::Complex { eta, k } => Vector::new(
+                microfacet::conductor(cosine, eta.x, k.x),
+                microfacet::conductor(cosine, eta.y, k.y),
+                microfacet::conductor(cosine, eta.z, k.z),
+            ),
+        };
+        reflectance * microfacet::masking_shadowing(&wo, &wi, alpha)
+            / microfacet::masking(&wo, alpha)
+    } else {
+        Vector::new(0.0, 0.0, 0.0)
+    };
+    Scatter::new(attenuation, Ray::new(hit.p, frame.to_world(&wi), r_in.time))
+}
+
+// Complex indices of refraction `(eta, k)` of common metals, averaged over the
+// red, green and blue parts of the spectrum.
+const PRESETS: [(&str, [f64; 3], [f64; 3]); 6] = [
+    (
+        "gold",
+        [0.143119, 0.374957, 1.44248],
+        [3.98316, 2.38572, 1.60322],
+    ),
+    (
+        "silver",
+        [0.155265, 0.116723, 0.138342],
+        [4.82835, 3.12225, 2.14696],
+    ),
+    (
+        "copper",
+        [0.200438, 0.924033, 1.10221],
+        [3.91295, 2.45285, 2.14219],
+    ),
+    (
+        "aluminium",
+        [1.65746, 0.880369, 0.521229],
+        [9.22387, 6.26952, 4.837],
+    ),
+    (
+        "chrome",
+        [4.36859, 2.9167, 1.6547],
+        [5.20564, 4.23136, 3.75483],
+    ),
+    ("iron", [2.95, 2.93, 2.65], [3.0, 2.93, 2.81]),
+];
+
+/// Complex index of refraction of the named metal, if it is in the table.
+pub fn preset(name: &str) -> Option<(Vector, Vector)> {
+    let name = name.to_lowercase();
+    let name = match name.as_str() {
+        "aluminum" => "aluminium",
+        "chromium" => "chrome",
+        name => name,
+    };
+    PRESETS
+        .iter()
+        .find(|preset| preset.0 == name)
+        .map(|&(_, eta, k)| {
+            (
+                Vector::new(eta[0], eta[1], eta[2]),
+                Vector::new(k[0], k[1], k[2]),
+            )
+        })
+}
@@ -1,45 +1,59 @@
 use rand::Rng;
 
 use crate::materials::Scatter;
+use crate::media::{Media, Medium};
 use crate::objects::HitRecord;
 use crate::ray::Ray;
 use crate::vector::Vector;
 
-pub fn scatter(ref_idx: f64, r_in: &Ray, hit: &HitRecord) -> Scatter {
-    let reflected = reflect(&r_in.direction, &hit.normal);
+pub fn scatter(
+    medium: &Medium,
+    r_in: &Ray,
+    hit: &HitRecord,
+    media: &mut Media,
+    wavelength: Option<f64>,
+) -> Scatter {
     let attenuation = Vector::new(1.0, 1.0, 1.0);
+    let entering = Vector::dot(&r_in.direction, &hit.normal) < 0.0;
 
-    let outward_normal;
-    let ni_over_nt;
-    let cosine;
-
-    if Vector::dot(&r_in.direction, &hit.normal) > 0.0 {
-        outward_normal = -hit.normal;
-        ni_over_nt = ref_idx;
-        cosine = ref_idx * Vector::dot(&r_in.direction, &hit.normal) / r_in.direction.length();
-    } else {
-        outward_normal = hit.normal;
-        ni_over_nt = 1.0 / ref_idx;
-        cosine = Vector::dot(&-r_in.direction, &hit.normal) / r_in.direction.length();
+    // Pass straight through surfaces hidden inside other media
+    let eta = match media.boundary(medium, entering, wavelength) {
+        Some(eta) => eta,
+        None => {
+            media.cross(medium, entering);
+            return Scatter::new(attenuation, Ray::new(hit.p, r_in.direction, r_in.time));
+        }
     };
 
+    let reflected = reflect(&r_in.direction, &hit.normal);
+    let outward_normal = if entering { hit.normal } else { -hit.normal };
+    let ni_over_nt = 1.0 / eta;
+
+    // Light arriving from the denser side is reflected as for the angle of
+    // the refracted ray
+    let cosine = Vector::dot(&-r_in.direction, &outward_normal) / r_in.direction.length()
+        * ni_over_nt.max(1.0);
+
     let mut rng = rand::thread_rng();
     let prob = rng.gen_range(0.0, 1.0);
-    let reflect_prob = schlick(cosine, ref_idx);
+    let reflect_prob = schlick(cosine, eta);
 
     if prob < reflect_prob {
-        Scatter::new(attenuation, Ray::new(hit.p, reflected))
+        Scatter::new(attenuation, Ray::new(hit.p, reflected, r_in.time))
     } else {
         let refraction = refract(&r_in.direction, &outward_normal, ni_over_nt);
         match refraction {
-            Some(refracted) => Scatter::new(attenuation, Ray::new(hit.p, refracted)),
-            None => Scatter::new(attenuation, Ray::new(hit.p, reflected)),
+            Some(refracted) => {
+                media.cross(medium, entering);
+                Scatter::new(attenuation, Ray::new(hit.p, refracted, r_in.time))
+            }
+            None => Scatter::new(attenuation, Ray::new(hit.p, reflected, r_in.time)),
         }
     }
 }
 
 fn reflect(v: &Vector, n: &Vector) -> Vector {
-    *v - 2.0 * Vector::dot(&v, n) * *n
+    *v - 2.0 * Vector::dot(v, n) * *n
 }
 
 fn refract(v: &Vector, n: &Vector, ni_over_nt: f64) -> Option<Vector> {
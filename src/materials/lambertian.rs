@@ -3,8 +3,8 @@ use crate::objects::HitRecord;
 use crate::ray::Ray;
 use crate::vector::Vector;
 
-pub fn scatter(albedo: Vector, _r_in: &Ray, hit: &HitRecord) -> Scatter {
+pub fn scatter(albedo: Vector, r_in: &Ray, hit: &HitRecord) -> Scatter {
     let target = hit.p + hit.normal + Vector::random();
-    let scattered = Ray::new(hit.p, target - hit.p);
+    let scattered = Ray::new(hit.p, target - hit.p, r_in.time);
     Scatter::new(albedo, scattered)
 }
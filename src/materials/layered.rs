@@ -0,0 +1,121 @@
+use rand::Rng;
+
+use crate::materials::microfacet::{self, Frame};
+use crate::materials::{Coating, Material, Scatter};
+use crate::media::Media;
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// Most crossings of the coat before light still trapped inside it is
+// given up as absorbed
+const MAX_BOUNCES: u32 = 16;
+
+/// Scatter off the `base` through a rough dielectric coat by following
+/// light as it is refracted into the coat, scattered by the base and
+/// reflected back down by the top of the coat until it leaves. The coat is
+/// too thin to displace the ray, so every bounce happens at the hit.
+pub fn scatter(
+    coating: &Coating,
+    base: &Material,
+    r_in: &Ray,
+    hit: &HitRecord,
+    media: &mut Media,
+    wavelength: Option<f64>,
+) -> Scatter {
+    // Light arriving from beneath, through a transmitting base, never
+    // reaches the coat
+    if Vector::dot(&r_in.direction, &hit.normal) > 0.0 {
+        return base.scatter_surface(r_in, hit, media, wavelength);
+    }
+
+    let mut rng = rand::thread_rng();
+    let frame = Frame::new(hit.normal, hit.tangent);
+    let wo = frame.to_local(&-r_in.direction.unit());
+    let alpha = microfacet::alpha(coating.roughness);
+
+    // Reflect off the top of the coat, or enter it
+    let h = microfacet::sample_visible_normal(&wo, alpha);
+    let reflectance = microfacet::dielectric(Vector::dot(&wo, &h), coating.ref_idx);
+    let refracted = microfacet::refract(&-wo, &h, coating.ref_idx);
+    let mut w = match refracted {
+        Some(w) if rng.gen::<f64>() >= reflectance => w,
+        _ => {
+            let wi = microfacet::reflect(&-wo, &h);
+            return exit(
+                &frame,
+                &wo,
+                &wi,
+                alpha,
+                Vector::new(1.0, 1.0, 1.0),
+                r_in,
+                hit,
+            );
+        }
+    };
+    if w.z >= 0.0 {
+        return Scatter::absorbed();
+    }
+    let mut attenuation = shadowing(&wo, &w, alpha);
+
+    for _ in 0..MAX_BOUNCES {
+        attenuation *= coating.transmittance(w.z);
+        let down = Ray::new(hit.p - frame.to_world(&w), frame.to_world(&w), r_in.time);
+        let scatter = base.scatter_surface(&down, hit, media, wavelength);
+        attenuation *= scatter.attenuation;
+
+        // Light transmitted by the base leaves the coat behind
+        let up = frame.to_local(&scatter.ray.direction.unit());
+        if up.z <= 0.0 {
+            return Scatter::new(attenuation, scatter.ray);
+        }
+        attenuation *= coating.transmittance(up.z);
+
+        // Seen from inside the coat, its top faces down
+        let flipped = Vector::new(-up.x, -up.y, up.z);
+        let h = microfacet::sample_visible_normal(&flipped, alpha);
+        let h = Vector::new(h.x, h.y, -h.z);
+        let reflectance = microfacet::dielectric(-Vector::dot(&up, &h), 1.0 / coating.ref_idx);
+        match microfacet::refract(&up, &h, 1.0 / coating.ref_idx) {
+            Some(wi) if rng.gen::<f64>() >= reflectance => {
+                if wi.z <= 0.0 {
+                    return Scatter::absorbed();
+                }
+                return exit(&frame, &-up, &wi, alpha, attenuation, r_in, hit);
+            }
+            _ => {
+                w = microfacet::reflect(&up, &h);
+                if w.z >= 0.0 {
+                    return Scatter::absorbed();
+                }
+                attenuation *= shadowing(&up, &w, alpha);
+            }
+        }
+    }
+    Scatter::absorbed()
+}
+
+// Fraction of microfacets seen from `wo` which are not shadowed from `wi`.
+// Shadowing is symmetric about the surface, so either side may be used.
+fn shadowing(wo: &Vector, wi: &Vector, alpha: f64) -> Vector {
+    let weight = microfacet::masking_shadowing(wo, wi, alpha) / microfacet::masking(wo, alpha);
+    Vector::new(weight, weight, weight)
+}
+
+// Leave the coat in the direction `wi`, shadowed as seen from `wo`.
+fn exit(
+    frame: &Frame,
+    wo: &Vector,
+    wi: &Vector,
+    alpha: f64,
+    attenuation: Vector,
+    r_in: &Ray,
+    hit: &HitRecord,
+) -> Scatter {
+    let attenuation = if wi.z > 0.0 {
+        attenuation * shadowing(wo, wi, alpha)
+    } else {
+        Vector::new(0.0, 0.0, 0.0)
+    };
+    Scatter::new(attenuation, Ray::new(hit.p, frame.to_world(wi), r_in.time))
+}
@@ -0,0 +1,166 @@
+use std::f64::consts::PI;
+
+use rand::Rng;
+
+use crate::vector::Vector;
+
+// Trowbridge-Reitz (GGX) microfacet distribution with Smith's
+// height-correlated masking-shadowing, sampled by visible normals after
+// Heitz, "Sampling the GGX Distribution of Visible Normals" (2018).
+//
+// Directions are in a local shading frame where the normal is the z axis.
+
+/// Orthonormal shading frame around a surface normal.
+pub struct Frame {
+    pub tangent: Vector,
+    pub bitangent: Vector,
+    pub normal: Vector,
+}
+
+impl Frame {
+    /// Frame around `normal`, which must be perpendicular to `tangent`.
+    pub fn new(normal: Vector, tangent: Vector) -> Frame {
+        Frame {
+            tangent,
+            bitangent: Vector::cross(&normal, &tangent),
+            normal,
+        }
+    }
+
+    pub fn to_local(&self, v: &Vector) -> Vector {
+        Vector::new(
+            Vector::dot(v, &self.tangent),
+            Vector::dot(v, &self.bitangent),
+            Vector::dot(v, &self.normal),
+        )
+    }
+
+    pub fn to_world(&self, v: &Vector) -> Vector {
+        v.x * self.tangent + v.y * self.bitangent + v.z * self.normal
+    }
+}
+
+/// Direction above the surface chosen with probability proportional to the
+/// cosine of its angle to the normal, as for diffuse reflection.
+pub fn sample_cosine() -> Vector {
+    let mut rng = rand::thread_rng();
+    let (u1, u2) = (rng.gen::<f64>(), rng.gen::<f64>());
+    let (r, phi) = (u1.sqrt(), 2.0 * PI * u2);
+    Vector::new(r * phi.cos(), r * phi.sin(), (1.0 - u1).sqrt())
+}
+
+/// Width of the distribution for a perceptually linear `roughness`, kept
+/// above zero so that smooth surfaces remain well defined. The floor is low
+/// enough that the long tail of GGX rarely sends a smooth surface's rays
+/// visibly far from the mirror direction.
+pub fn alpha(roughness: f64) -> f64 {
+    (roughness * roughness).max(1e-6)
+}
+
+// Smith's auxiliary function for the direction `w`.
+fn lambda(w: &Vector, alpha: f64) -> f64 {
+    let cos2 = w.z * w.z;
+    if cos2 == 0.0 {
+        return f64::INFINITY;
+    }
+    let tan2 = (1.0 - cos2).max(0.0) / cos2;
+    0.5 * (-1.0 + (1.0 + alpha * alpha * tan2).sqrt())
+}
+
+/// Fraction of microfacets facing `w` which are visible from it.
+pub fn masking(w: &Vector, alpha: f64) -> f64 {
+    1.0 / (1.0 + lambda(w, alpha))
+}
+
+/// Fraction of microfacets visible from both `wo` and `wi`.
+pub fn masking_shadowing(wo: &Vector, wi: &Vector, alpha: f64) -> f64 {
+    1.0 / (1.0 + lambda(wo, alpha) + lambda(wi, alpha))
+}
+
+/// Sample a microfacet normal in proportion to its visible area from `wo`,
+/// which must be above the surface.
+pub fn sample_visible_normal(wo: &Vector, alpha: f64) -> Vector {
+    let mut rng = rand::thread_rng();
+    let (u1, u2) = (rng.gen::<f64>(), rng.gen::<f64>());
+
+    // Stretch the view direction to the hemisphere configuration
+    let vh = Vector::new(alpha * wo.x, alpha * wo.y, wo.z).unit();
+    let len2 = vh.x * vh.x + vh.y * vh.y;
+    let t1 = if len2 > 0.0 {
+        Vector::new(-vh.y, vh.x, 0.0) / len2.sqrt()
+    } else {
+        Vector::new(1.0, 0.0, 0.0)
+    };
+    let t2 = Vector::cross(&vh, &t1);
+
+    // Uniform point on the projected disk, warped to the visible half
+    let r = u1.sqrt();
+    let phi = 2.0 * PI * u2;
+    let p1 = r * phi.cos();
+    let s = 0.5 * (1.0 + vh.z);
+    let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+    let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+    // Unstretch back to the ellipsoid configuration
+    Vector::new(alpha * nh.x, alpha * nh.y, nh.z.max(1e-6)).unit()
+}
+
+/// Schlick's approximation to the Fresnel reflectance of each channel at
+/// normal incidence `f0`.
+pub fn schlick(cosine: f64, f0: Vector) -> Vector {
+    let weight = (1.0 - cosine).max(0.0).powi(5);
+    f0 + weight * (Vector::new(1.0, 1.0, 1.0) - f0)
+}
+
+/// Exact Fresnel reflectance of unpolarised light arriving at a metal of
+/// complex index of refraction `eta + i k`.
+pub fn conductor(cosine: f64, eta: f64, k: f64) -> f64 {
+    let cos2 = cosine.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let (eta2, k2) = (eta * eta, k * k);
+
+    let t0 = eta2 - k2 - sin2;
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+    let t1 = a2_plus_b2 + cos2;
+    let a = (0.5 * (a2_plus_b2 + t0)).max(0.0).sqrt();
+    let t2 = 2.0 * cosine.clamp(0.0, 1.0) * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + sin2 * sin2;
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rp + rs)
+}
+
+/// Exact Fresnel reflectance of unpolarised light arriving at a dielectric,
+/// where `eta` is the index of refraction on the far side of the interface
+/// relative to the near side. Total internal reflection gives 1.
+pub fn dielectric(cosine: f64, eta: f64) -> f64 {
+    let cos_i = cosine.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let rs = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    let rp = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    0.5 * (rs * rs + rp * rp)
+}
+
+pub fn reflect(v: &Vector, n: &Vector) -> Vector {
+    *v - 2.0 * Vector::dot(v, n) * *n
+}
+
+/// Refract the unit direction `v` through a surface with unit normal `n`
+/// facing against it, or `None` on total internal reflection. `eta` is the
+/// index of refraction on the far side relative to the near side.
+pub fn refract(v: &Vector, n: &Vector, eta: f64) -> Option<Vector> {
+    let cos_i = -Vector::dot(v, n);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(*v / eta + (cos_i / eta - cos_t) * *n)
+}
@@ -0,0 +1,45 @@
+use crate::materials::microfacet::{self, Frame};
+use crate::materials::Scatter;
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Reflect diffusely off a surface of tiny Lambertian facets whose slopes
+/// have a standard deviation of `sigma` radians, using the qualitative model
+/// of Oren and Nayar, "Generalization of Lambert's Reflectance Model" (1994).
+/// Rough surfaces scatter more light back towards where it came from, and
+/// are flatter looking than Lambertian ones.
+pub fn scatter(albedo: Vector, sigma: f64, r_in: &Ray, hit: &HitRecord) -> Scatter {
+    // Shade the side of the surface the ray arrives from
+    let normal = if Vector::dot(&r_in.direction, &hit.normal) > 0.0 {
+        -hit.normal
+    } else {
+        hit.normal
+    };
+    let frame = Frame::new(normal, hit.tangent);
+    let wo = frame.to_local(&-r_in.direction.unit());
+    let wi = microfacet::sample_cosine();
+
+    let sigma2 = sigma * sigma;
+    let a = 1.0 - 0.5 * sigma2 / (sigma2 + 0.33);
+    let b = 0.45 * sigma2 / (sigma2 + 0.09);
+
+    let sin_i = (1.0 - wi.z * wi.z).max(0.0).sqrt();
+    let sin_o = (1.0 - wo.z * wo.z).max(0.0).sqrt();
+    let cos_phi = if sin_i > 1e-4 && sin_o > 1e-4 {
+        ((wi.x * wo.x + wi.y * wo.y) / (sin_i * sin_o)).max(0.0)
+    } else {
+        0.0
+    };
+    let (sin_alpha, tan_beta) = if wi.z.abs() > wo.z.abs() {
+        (sin_o, sin_i / wi.z.abs())
+    } else {
+        (sin_i, sin_o / wo.z.abs())
+    };
+
+    // Sampling by cosine cancels the cosine and 1/π of the reflectance. The
+    // qualitative model is unbounded at grazing angles, so is clamped to
+    // keep the surface from reflecting more light than it receives.
+    let attenuation = albedo * (a + b * cos_phi * sin_alpha * tan_beta).min(1.0);
+    Scatter::new(attenuation, Ray::new(hit.p, frame.to_world(&wi), r_in.time))
+}
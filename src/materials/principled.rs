@@ -0,0 +1,100 @@
+use rand::Rng;
+
+use crate::materials::microfacet::{self, Frame};
+use crate::materials::{rough_dielectric, Principled, Scatter};
+use crate::media::Media;
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// Index of refraction of the clearcoat, as for most varnishes
+const CLEARCOAT_REF_IDX: f64 = 1.5;
+
+/// Scatter off the layers of the principled material by choosing one of
+/// them at random: the clearcoat, then glass-like transmission, metal, and
+/// finally a dielectric specular layer over a diffuse base with sheen.
+/// Layers are chosen in proportion to their weights and Fresnel
+/// reflectances, so each lobe only carries its own colour and shadowing.
+pub fn scatter(
+    principled: &Principled,
+    r_in: &Ray,
+    hit: &HitRecord,
+    media: &mut Media,
+    wavelength: Option<f64>,
+) -> Scatter {
+    let mut rng = rand::thread_rng();
+    let white = Vector::new(1.0, 1.0, 1.0);
+    let ref_idx = principled.ref_idx();
+
+    // Shade the side of the surface the ray arrives from
+    let entering = Vector::dot(&r_in.direction, &hit.normal) < 0.0;
+    let normal = if entering { hit.normal } else { -hit.normal };
+    let frame = Frame::new(normal, hit.tangent);
+    let wo = frame.to_local(&-r_in.direction.unit());
+
+    if entering && principled.clearcoat > 0.0 {
+        let alpha = microfacet::alpha(principled.clearcoat_roughness);
+        let h = microfacet::sample_visible_normal(&wo, alpha);
+        let reflectance = microfacet::dielectric(Vector::dot(&wo, &h), CLEARCOAT_REF_IDX);
+        if rng.gen::<f64>() < principled.clearcoat * reflectance {
+            return reflect(&frame, &wo, &h, alpha, white, r_in, hit);
+        }
+    }
+
+    // Transmitted light enters the object as through rough glass, tinted by
+    // the base colour
+    if rng.gen::<f64>() < principled.transmission * (1.0 - principled.metallic) {
+        let medium = principled.medium();
+        let roughness = principled.roughness;
+        let mut scatter =
+            rough_dielectric::scatter(&medium, roughness, r_in, hit, media, wavelength);
+        if entering && Vector::dot(&scatter.ray.direction, &hit.normal) < 0.0 {
+            scatter.attenuation *= principled.base_colour;
+        }
+        return scatter;
+    }
+
+    let alpha = microfacet::alpha(principled.roughness);
+    let h = microfacet::sample_visible_normal(&wo, alpha);
+    let cosine = Vector::dot(&wo, &h);
+    if rng.gen::<f64>() < principled.metallic {
+        let reflectance = microfacet::schlick(cosine, principled.base_colour);
+        return reflect(&frame, &wo, &h, alpha, reflectance, r_in, hit);
+    }
+    if rng.gen::<f64>() < microfacet::dielectric(cosine, ref_idx) {
+        return reflect(&frame, &wo, &h, alpha, white, r_in, hit);
+    }
+
+    // Light passing the specular layer is scattered diffusely, with sheen
+    // taking over from the base colour at grazing angles
+    let wi = microfacet::sample_cosine();
+    let cos_d = Vector::dot(&wi, &(wi + wo).unit());
+    let sheen = principled.sheen * (1.0 - cos_d).max(0.0).powi(5);
+    Scatter::new(
+        (1.0 - sheen) * principled.base_colour + sheen * principled.sheen_colour(),
+        Ray::new(hit.p, frame.to_world(&wi), r_in.time),
+    )
+}
+
+// Mirror `wo` about the microfacet normal `h`, weighted by the `colour` of
+// the layer and the shadowing of the reflected ray.
+fn reflect(
+    frame: &Frame,
+    wo: &Vector,
+    h: &Vector,
+    alpha: f64,
+    colour: Vector,
+    r_in: &Ray,
+    hit: &HitRecord,
+) -> Scatter {
+    let wi = microfacet::reflect(&-*wo, h);
+    let weight = if wi.z > 0.0 {
+        microfacet::masking_shadowing(wo, &wi, alpha) / microfacet::masking(wo, alpha)
+    } else {
+        0.0
+    };
+    Scatter::new(
+        weight * colour,
+        Ray::new(hit.p, frame.to_world(&wi), r_in.time),
+    )
+}
@@ -0,0 +1,65 @@
+use rand::Rng;
+
+use crate::materials::microfacet::{self, Frame};
+use crate::materials::Scatter;
+use crate::media::{Media, Medium};
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Reflect off or refract through a rough dielectric interface, after
+/// Walter et al., "Microfacet Models for Refraction through Rough Surfaces"
+/// (2007). A visible GGX microfacet is sampled, then the ray is reflected or
+/// refracted through it with the probability given by its Fresnel
+/// reflectance, leaving only the shadowing of the scattered ray as weight.
+pub fn scatter(
+    medium: &Medium,
+    roughness: f64,
+    r_in: &Ray,
+    hit: &HitRecord,
+    media: &mut Media,
+    wavelength: Option<f64>,
+) -> Scatter {
+    let entering = Vector::dot(&r_in.direction, &hit.normal) < 0.0;
+
+    // Pass straight through surfaces hidden inside other media
+    let eta = match media.boundary(medium, entering, wavelength) {
+        Some(eta) => eta,
+        None => {
+            media.cross(medium, entering);
+            let attenuation = Vector::new(1.0, 1.0, 1.0);
+            return Scatter::new(attenuation, Ray::new(hit.p, r_in.direction, r_in.time));
+        }
+    };
+
+    // Shade the side of the surface the ray arrives from
+    let normal = if entering { hit.normal } else { -hit.normal };
+    let frame = Frame::new(normal, hit.tangent);
+    let wo = frame.to_local(&-r_in.direction.unit());
+
+    let alpha = microfacet::alpha(roughness);
+    let h = microfacet::sample_visible_normal(&wo, alpha);
+    let reflectance = microfacet::dielectric(Vector::dot(&wo, &h), eta);
+
+    let mut rng = rand::thread_rng();
+    let refracted = if rng.gen::<f64>() < reflectance {
+        None
+    } else {
+        microfacet::refract(&-wo, &h, eta)
+    };
+
+    // Rays ending up on the wrong side of the surface are absorbed
+    let wi = refracted.unwrap_or_else(|| microfacet::reflect(&-wo, &h));
+    let weight = if (wi.z > 0.0) == refracted.is_none() {
+        if refracted.is_some() {
+            media.cross(medium, entering);
+        }
+        microfacet::masking_shadowing(&wo, &wi, alpha) / microfacet::masking(&wo, alpha)
+    } else {
+        0.0
+    };
+    Scatter::new(
+        Vector::new(weight, weight, weight),
+        Ray::new(hit.p, frame.to_world(&wi), r_in.time),
+    )
+}
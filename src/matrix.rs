@@ -0,0 +1,298 @@
+use std::ops;
+
+use crate::vector::Vector;
+
+/// 4x4 affine transformation matrix, stored in row-major order.
+#[derive(Copy, Clone, Debug)]
+pub struct Matrix {
+    pub m: [[f64; 4]; 4],
+}
+
+// Construction
+impl Matrix {
+    pub fn new(m: [[f64; 4]; 4]) -> Matrix {
+        Matrix { m }
+    }
+
+    pub fn identity() -> Matrix {
+        Matrix::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn translation(offset: Vector) -> Matrix {
+        Matrix::new([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    pub fn scaling(factor: Vector) -> Matrix {
+        Matrix::new([
+            [factor.x, 0.0, 0.0, 0.0],
+            [0.0, factor.y, 0.0, 0.0],
+            [0.0, 0.0, factor.z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// Rotation by `angle` degrees counter-clockwise about `axis`.
+    pub fn rotation(axis: Vector, angle: f64) -> Matrix {
+        let a = axis.unit();
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let t = 1.0 - cos;
+        Matrix::new([
+            [
+                t * a.x * a.x + cos,
+                t * a.x * a.y - sin * a.z,
+                t * a.x * a.z + sin * a.y,
+                0.0,
+            ],
+            [
+                t * a.x * a.y + sin * a.z,
+                t * a.y * a.y + cos,
+                t * a.y * a.z - sin * a.x,
+                0.0,
+            ],
+            [
+                t * a.x * a.z - sin * a.y,
+                t * a.y * a.z + sin * a.x,
+                t * a.z * a.z + cos,
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+}
+
+// Computed properties
+impl Matrix {
+    pub fn transpose(&self) -> Matrix {
+        let mut result = Matrix::identity();
+        for i in 0..4 {
+            for j in 0..4 {
+                result.m[i][j] = self.m[j][i];
+            }
+        }
+        result
+    }
+
+    /// Invert the matrix by Gauss-Jordan elimination, returning `None` if it
+    /// is singular.
+    pub fn inverse(&self) -> Option<Matrix> {
+        let mut a = self.m;
+        let mut inv = Matrix::identity().m;
+
+        for col in 0..4 {
+            // Partial pivoting for numerical stability
+            let pivot = (col..4)
+                .max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))
+                .unwrap();
+            if a[pivot][col].abs() < 1e-12 || a[pivot][col].is_nan() {
+                return None;
+            }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row != col {
+                    let factor = a[row][col];
+                    for j in 0..4 {
+                        a[row][j] -= factor * a[col][j];
+                        inv[row][j] -= factor * inv[col][j];
+                    }
+                }
+            }
+        }
+
+        Some(Matrix::new(inv))
+    }
+}
+
+// Transformations
+impl Matrix {
+    pub fn transform_point(&self, p: &Vector) -> Vector {
+        let m = &self.m;
+        Vector::new(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    pub fn transform_vector(&self, v: &Vector) -> Vector {
+        let m = &self.m;
+        Vector::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    /// Transform a surface normal, given that `self` is the inverse of the
+    /// transformation applied to the surface.
+    pub fn transform_normal(&self, n: &Vector) -> Vector {
+        self.transpose().transform_vector(n)
+    }
+}
+
+// Operator overloading
+
+impl ops::Index<usize> for Matrix {
+    type Output = [f64; 4];
+
+    fn index(&self, index: usize) -> &[f64; 4] {
+        &self.m[index]
+    }
+}
+
+impl ops::IndexMut<usize> for Matrix {
+    fn index_mut(&mut self, index: usize) -> &mut [f64; 4] {
+        &mut self.m[index]
+    }
+}
+
+impl ops::Mul for Matrix {
+    type Output = Matrix;
+
+    fn mul(self, rhs: Matrix) -> Matrix {
+        let mut result = Matrix::new([[0.0; 4]; 4]);
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    result.m[i][j] += self.m[i][k] * rhs.m[k][j];
+                }
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    // Utility function to check the approximate equality of two vectors.
+    //
+    // Direct equality comparison between two vectors is unavailable due to
+    // the underlying float representation.
+    fn vec_approx_equal(v1: Vector, v2: Vector) {
+        assert_approx_eq!(v1.x, v2.x);
+        assert_approx_eq!(v1.y, v2.y);
+        assert_approx_eq!(v1.z, v2.z);
+    }
+
+    fn mat_approx_equal(m1: Matrix, m2: Matrix) {
+        for i in 0..4 {
+            for j in 0..4 {
+                assert_approx_eq!(m1[i][j], m2[i][j]);
+            }
+        }
+    }
+
+    // Construction
+
+    #[test]
+    fn matrix_translation() {
+        let given = Matrix::translation(Vector::new(1.0, 2.0, 3.0));
+        vec_approx_equal(
+            given.transform_point(&Vector::new(1.0, 1.0, 1.0)),
+            Vector::new(2.0, 3.0, 4.0),
+        );
+        vec_approx_equal(
+            given.transform_vector(&Vector::new(1.0, 1.0, 1.0)),
+            Vector::new(1.0, 1.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn matrix_scaling() {
+        let given = Matrix::scaling(Vector::new(1.0, 2.0, 3.0));
+        vec_approx_equal(
+            given.transform_point(&Vector::new(1.0, 1.0, 1.0)),
+            Vector::new(1.0, 2.0, 3.0),
+        );
+    }
+
+    #[test]
+    fn matrix_rotation() {
+        let given = Matrix::rotation(Vector::new(0.0, 0.0, 2.0), 90.0);
+        vec_approx_equal(
+            given.transform_vector(&Vector::new(1.0, 0.0, 0.0)),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+
+        let given = Matrix::rotation(Vector::new(0.0, 1.0, 0.0), 90.0);
+        vec_approx_equal(
+            given.transform_vector(&Vector::new(1.0, 0.0, 0.0)),
+            Vector::new(0.0, 0.0, -1.0),
+        );
+    }
+
+    // Computed properties
+
+    #[test]
+    fn matrix_transpose() {
+        let given = Matrix::translation(Vector::new(1.0, 2.0, 3.0)).transpose();
+        assert_approx_eq!(given[3][0], 1.0);
+        assert_approx_eq!(given[3][1], 2.0);
+        assert_approx_eq!(given[3][2], 3.0);
+        assert_approx_eq!(given[0][3], 0.0);
+    }
+
+    #[test]
+    fn matrix_inverse() {
+        let given = Matrix::translation(Vector::new(1.0, 2.0, 3.0))
+            * Matrix::rotation(Vector::new(1.0, 1.0, 0.0), 30.0)
+            * Matrix::scaling(Vector::new(2.0, 3.0, 4.0));
+        let inverse = given.inverse().unwrap();
+        mat_approx_equal(given * inverse, Matrix::identity());
+        mat_approx_equal(inverse * given, Matrix::identity());
+    }
+
+    #[test]
+    fn matrix_inverse_singular() {
+        let given = Matrix::scaling(Vector::new(1.0, 0.0, 1.0));
+        assert!(given.inverse().is_none());
+        let given = Matrix::scaling(Vector::new(1.0, f64::NAN, 1.0));
+        assert!(given.inverse().is_none());
+    }
+
+    // Transformations
+
+    #[test]
+    fn matrix_transform_normal() {
+        // Squashing a 45 degree slope makes its normal steeper
+        let transform = Matrix::scaling(Vector::new(1.0, 0.5, 1.0));
+        let inverse = transform.inverse().unwrap();
+        let given = inverse
+            .transform_normal(&Vector::new(-1.0, 1.0, 0.0))
+            .unit();
+        let expected = Vector::new(-1.0, 2.0, 0.0).unit();
+        vec_approx_equal(given, expected);
+    }
+
+    // Operator overloading
+
+    #[test]
+    fn matrix_mul() {
+        let given = Matrix::translation(Vector::new(1.0, 0.0, 0.0))
+            * Matrix::scaling(Vector::new(2.0, 2.0, 2.0));
+        vec_approx_equal(
+            given.transform_point(&Vector::new(1.0, 1.0, 1.0)),
+            Vector::new(3.0, 2.0, 2.0),
+        );
+    }
+}
@@ -0,0 +1,256 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::vector::Vector;
+
+// Identity of the next medium to be constructed
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Variation of the index of refraction of a dielectric with wavelength,
+/// which splits white light into its colours. Wavelengths are given in
+/// micrometres.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dispersion {
+    /// Cauchy's equation, `n = a + b / λ²`.
+    Cauchy { a: f64, b: f64 },
+    /// Sellmeier's equation, `n² = 1 + Σ b λ² / (λ² - c)`, as given for
+    /// optical glasses by their manufacturers.
+    Sellmeier { b: [f64; 3], c: [f64; 3] },
+}
+
+impl Dispersion {
+    /// Index of refraction at a `wavelength` in nanometres.
+    pub fn ref_idx(&self, wavelength: f64) -> f64 {
+        let l2 = (wavelength / 1000.0).powi(2);
+        match *self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let sum: f64 = b.iter().zip(c.iter()).map(|(b, c)| b * l2 / (l2 - c)).sum();
+                (1.0 + sum).sqrt()
+            }
+        }
+    }
+}
+
+/// Interior of a dielectric, which light travels through between its
+/// surfaces.
+#[derive(Copy, Clone, Debug)]
+pub struct Medium {
+    /// Identity of the medium, shared by its copies, which tells apart
+    /// overlapping objects of the same properties.
+    pub id: u64,
+    pub ref_idx: f64,
+    pub absorption: Vector,
+    pub priority: u32,
+    /// Dispersion seen by paths of a single wavelength, in place of
+    /// `ref_idx`.
+    pub dispersion: Option<Dispersion>,
+}
+
+impl Medium {
+    /// Medium of an identity distinct from every other one constructed.
+    pub fn new(ref_idx: f64, absorption: Vector, priority: u32) -> Medium {
+        Medium {
+            id: Medium::new_id(),
+            ref_idx,
+            absorption,
+            priority,
+            dispersion: None,
+        }
+    }
+
+    /// Index of refraction at the `wavelength` of the path, if any.
+    pub fn ref_idx_at(&self, wavelength: Option<f64>) -> f64 {
+        match (self.dispersion, wavelength) {
+            (Some(dispersion), Some(wavelength)) => dispersion.ref_idx(wavelength),
+            _ => self.ref_idx,
+        }
+    }
+
+    // Identity not yet given to any medium.
+    pub(crate) fn new_id() -> u64 {
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn same(&self, other: &Medium) -> bool {
+        self.id == other.id
+    }
+}
+
+/// Media a path is currently inside, after Schmidt and Budge, "Simple
+/// Nested Dielectrics in Ray Traced Images" (2002).
+///
+/// Where media overlap, such as liquid filling a glass, the one of highest
+/// priority fills the overlap and the surfaces of the others inside it are
+/// ignored. Among equal priorities, the medium entered last wins. Outside
+/// every medium is air, of index of refraction 1.
+#[derive(Clone, Debug, Default)]
+pub struct Media {
+    stack: Vec<Medium>,
+}
+
+impl Media {
+    pub fn new() -> Media {
+        Media { stack: vec![] }
+    }
+
+    /// Medium filling the current point of the path, if any.
+    pub fn current(&self) -> Option<&Medium> {
+        let mut current: Option<&Medium> = None;
+        for medium in self.stack.iter() {
+            let wins = match current {
+                Some(current) => medium.priority >= current.priority,
+                None => true,
+            };
+            if wins {
+                current = Some(medium);
+            }
+        }
+        current
+    }
+
+    /// Index of refraction beyond the surface of `medium` relative to the
+    /// current one, when `entering` or leaving it, for a path of the given
+    /// `wavelength` if any. Surfaces hidden inside a medium of higher
+    /// priority are not real interfaces and give `None`.
+    pub fn boundary(
+        &self,
+        medium: &Medium,
+        entering: bool,
+        wavelength: Option<f64>,
+    ) -> Option<f64> {
+        let ref_idx =
+            |medium: Option<&Medium>| medium.map_or(1.0, |medium| medium.ref_idx_at(wavelength));
+
+        // Leaving a medium never entered, such as one the path started in
+        if !entering && !self.stack.iter().any(|m| m.same(medium)) {
+            return Some(ref_idx(self.current()) / medium.ref_idx_at(wavelength));
+        }
+
+        let mut beyond = self.clone();
+        beyond.cross(medium, entering);
+        match (self.current(), beyond.current()) {
+            (Some(before), Some(after)) if before.same(after) => None,
+            (before, after) => Some(ref_idx(after) / ref_idx(before)),
+        }
+    }
+
+    /// Record the path passing through the surface of `medium`.
+    pub fn cross(&mut self, medium: &Medium, entering: bool) {
+        if entering {
+            self.stack.push(*medium);
+        } else if let Some(i) = self.stack.iter().rposition(|m| m.same(medium)) {
+            self.stack.remove(i);
+        }
+    }
+
+    /// Fraction of light left by Beer-Lambert absorption in the current
+    /// medium after travelling `distance`.
+    pub fn transmittance(&self, distance: f64) -> Vector {
+        match self.current() {
+            Some(medium) => Vector::new(
+                (-medium.absorption.x * distance).exp(),
+                (-medium.absorption.y * distance).exp(),
+                (-medium.absorption.z * distance).exp(),
+            ),
+            None => Vector::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn medium(ref_idx: f64, priority: u32) -> Medium {
+        Medium::new(ref_idx, Vector::new(0.0, 0.0, 0.0), priority)
+    }
+
+    #[test]
+    fn medium_same() {
+        // Media of the same properties are told apart, but not their copies
+        let glass = medium(1.5, 0);
+        let copy = glass;
+        assert!(glass.same(&copy));
+        assert!(!glass.same(&medium(1.5, 0)));
+
+        let mut media = Media::new();
+        media.cross(&glass, true);
+        media.cross(&medium(1.5, 0), true);
+        media.cross(&copy, false);
+        assert_eq!(media.stack.len(), 1);
+        assert!(!media.stack[0].same(&glass));
+    }
+
+    #[test]
+    fn media_boundary() {
+        let water = medium(1.33, 0);
+        let bubble = medium(1.0, 0);
+        let mut media = Media::new();
+        assert_approx_eq!(media.boundary(&water, true, None).unwrap(), 1.33);
+        media.cross(&water, true);
+
+        // Entering an air bubble from water
+        assert_approx_eq!(media.boundary(&bubble, true, None).unwrap(), 1.0 / 1.33);
+        media.cross(&bubble, true);
+        assert_approx_eq!(media.current().unwrap().ref_idx, 1.0);
+        assert_approx_eq!(media.boundary(&bubble, false, None).unwrap(), 1.33);
+        media.cross(&bubble, false);
+        assert_approx_eq!(media.boundary(&water, false, None).unwrap(), 1.0 / 1.33);
+        media.cross(&water, false);
+        assert!(media.current().is_none());
+    }
+
+    #[test]
+    fn media_boundary_priority() {
+        // Liquid overlapping the walls of a glass of higher priority
+        let glass = medium(1.5, 1);
+        let liquid = medium(1.33, 0);
+        let mut media = Media::new();
+        media.cross(&glass, true);
+        assert!(media.boundary(&liquid, true, None).is_none());
+        media.cross(&liquid, true);
+        assert_approx_eq!(media.current().unwrap().ref_idx, 1.5);
+        assert_approx_eq!(media.boundary(&glass, false, None).unwrap(), 1.33 / 1.5);
+        media.cross(&glass, false);
+        assert_approx_eq!(media.current().unwrap().ref_idx, 1.33);
+
+        // Leaving a medium never entered leads to the current one
+        assert_approx_eq!(media.boundary(&glass, false, None).unwrap(), 1.33 / 1.5);
+    }
+
+    #[test]
+    fn media_transmittance() {
+        let mut media = Media::new();
+        let given = media.transmittance(2.0);
+        assert_approx_eq!(given.x, 1.0);
+
+        media.cross(&Medium::new(1.5, Vector::new(0.5, 1.0, 0.0), 0), true);
+        let given = media.transmittance(2.0);
+        assert_approx_eq!(given.x, (-1.0f64).exp());
+        assert_approx_eq!(given.y, (-2.0f64).exp());
+        assert_approx_eq!(given.z, 1.0);
+    }
+
+    #[test]
+    fn media_dispersion() {
+        // Schott N-BK7 glass
+        let bk7 = Dispersion::Sellmeier {
+            b: [1.03961212, 0.231792344, 1.01046945],
+            c: [0.00600069867, 0.0200179144, 103.560653],
+        };
+        assert_approx_eq!(bk7.ref_idx(587.6), 1.5168, 1e-4);
+        assert!(bk7.ref_idx(450.0) > bk7.ref_idx(650.0));
+
+        let cauchy = Dispersion::Cauchy { a: 1.5, b: 0.01 };
+        assert_approx_eq!(cauchy.ref_idx(500.0), 1.54);
+
+        // Without a wavelength, the fixed index of refraction is used
+        let mut glass = medium(1.5, 0);
+        glass.dispersion = Some(cauchy);
+        assert_approx_eq!(glass.ref_idx_at(None), 1.5);
+        assert_approx_eq!(glass.ref_idx_at(Some(500.0)), 1.54);
+        let media = Media::new();
+        assert_approx_eq!(media.boundary(&glass, true, Some(500.0)).unwrap(), 1.54);
+    }
+}
@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::vector::Vector;
+
+/// Triangle mesh with optional per-vertex attributes, which are interpolated
+/// across each face when it is hit.
+///
+/// Faces are wound counter-clockwise when seen from outside. Without
+/// normals, faces are flat shaded; without UVs, the barycentric coordinates
+/// of the hit are used instead.
+#[derive(Clone, Debug)]
+pub struct Mesh {
+    pub positions: Vec<Vector>,
+    pub normals: Option<Vec<Vector>>,
+    pub uvs: Option<Vec<(f64, f64)>>,
+    pub tangents: Option<Vec<Vector>>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+// Construction
+impl Mesh {
+    pub fn new(positions: Vec<Vector>, faces: Vec<[usize; 3]>) -> Mesh {
+        Mesh {
+            positions,
+            normals: None,
+            uvs: None,
+            tangents: None,
+            faces,
+        }
+    }
+
+    /// Read a mesh from a Wavefront OBJ file.
+    pub fn load_obj<P: AsRef<Path>>(path: P) -> io::Result<Mesh> {
+        Mesh::parse_obj(BufReader::new(File::open(path)?))
+    }
+
+    /// Parse the vertices and faces of Wavefront OBJ source, ignoring
+    /// groups, materials and other statements. Polygons are split into fans
+    /// of triangles.
+    ///
+    /// Normals are generated if the source lacks them, and tangents whenever
+    /// it has UVs.
+    pub fn parse_obj<R: BufRead>(reader: R) -> io::Result<Mesh> {
+        let obj = read_obj(reader)?;
+
+        // Each distinct combination of position, UV and normal becomes a
+        // vertex of the mesh
+        let mut vertices: HashMap<Corner, usize> = HashMap::new();
+        let mut corners = vec![];
+        let mut faces = vec![];
+        for polygon in obj.polygons.iter() {
+            let polygon: Vec<usize> = polygon
+                .iter()
+                .map(|&key| {
+                    *vertices.entry(key).or_insert_with(|| {
+                        corners.push(key);
+                        corners.len() - 1
+                    })
+                })
+                .collect();
+            for i in 1..polygon.len() - 1 {
+                faces.push([polygon[0], polygon[i], polygon[i + 1]]);
+            }
+        }
+
+        // Attributes are only kept if every vertex has them
+        let mut mesh = Mesh::new(corners.iter().map(|c| obj.positions[c.0]).collect(), faces);
+        mesh.uvs = corners.iter().map(|c| c.1.map(|i| obj.uvs[i])).collect();
+        mesh.normals = corners
+            .iter()
+            .map(|c| c.2.map(|i| obj.normals[i].unit()))
+            .collect();
+
+        if mesh.normals.is_none() {
+            mesh.generate_normals();
+        }
+        if mesh.uvs.is_some() {
+            mesh.generate_tangents();
+        }
+        Ok(mesh)
+    }
+}
+
+// Attribute generation
+impl Mesh {
+    /// Smooth normals averaged from the faces around each vertex, weighted
+    /// by the angle of each face at the vertex so that the result does not
+    /// depend on how the surface is triangulated.
+    pub fn generate_normals(&mut self) {
+        let mut normals = vec![Vector::new(0.0, 0.0, 0.0); self.positions.len()];
+        for face in self.faces.iter() {
+            let p = face.map(|i| self.positions[i]);
+            let n = Vector::cross(&(p[1] - p[0]), &(p[2] - p[0]));
+            if n.length() == 0.0 {
+                continue;
+            }
+            let n = n.unit();
+
+            for k in 0..3 {
+                let e1 = p[(k + 1) % 3] - p[k];
+                let e2 = p[(k + 2) % 3] - p[k];
+                let cos = Vector::dot(&e1.unit(), &e2.unit()).clamp(-1.0, 1.0);
+                normals[face[k]] += cos.acos() * n;
+            }
+        }
+
+        for n in normals.iter_mut() {
+            if n.length() > 0.0 {
+                *n = n.unit();
+            }
+        }
+        self.normals = Some(normals);
+    }
+
+    /// Tangents pointing along increasing u, averaged from the faces around
+    /// each vertex and made perpendicular to its normal, after Lengyel's
+    /// method. Normals are generated first if there are none.
+    ///
+    /// Panics if the mesh has no UVs.
+    pub fn generate_tangents(&mut self) {
+        if self.normals.is_none() {
+            self.generate_normals();
+        }
+        let uvs = self.uvs.as_ref().expect("Tangents require UVs!");
+        let normals = self.normals.as_ref().unwrap();
+
+        let mut tangents = vec![Vector::new(0.0, 0.0, 0.0); self.positions.len()];
+        for face in self.faces.iter() {
+            let p = face.map(|i| self.positions[i]);
+            let uv = face.map(|i| uvs[i]);
+            let (du1, dv1) = (uv[1].0 - uv[0].0, uv[1].1 - uv[0].1);
+            let (du2, dv2) = (uv[2].0 - uv[0].0, uv[2].1 - uv[0].1);
+            let det = du1 * dv2 - du2 * dv1;
+            if det == 0.0 {
+                continue;
+            }
+
+            let tangent = ((p[1] - p[0]) * dv2 - (p[2] - p[0]) * dv1) / det;
+            for &i in face.iter() {
+                tangents[i] += tangent;
+            }
+        }
+
+        // Gram-Schmidt against the normal, falling back to an arbitrary
+        // perpendicular where the UVs are degenerate
+        for (t, n) in tangents.iter_mut().zip(normals.iter()) {
+            let perpendicular = *t - Vector::dot(n, t) * *n;
+            *t = if perpendicular.length() > 1e-12 {
+                perpendicular.unit()
+            } else {
+                n.tangents().0
+            };
+        }
+        self.tangents = Some(tangents);
+    }
+}
+
+// Zero-based position, UV and normal indices of a polygon corner.
+pub(crate) type Corner = (usize, Option<usize>, Option<usize>);
+
+// Contents of a Wavefront OBJ file, with polygons left as they are.
+pub(crate) struct Obj {
+    pub positions: Vec<Vector>,
+    pub uvs: Vec<(f64, f64)>,
+    pub normals: Vec<Vector>,
+    pub polygons: Vec<Vec<Corner>>,
+}
+
+pub(crate) fn read_obj<R: BufRead>(reader: R) -> io::Result<Obj> {
+    let mut obj = Obj {
+        positions: vec![],
+        uvs: vec![],
+        normals: vec![],
+        polygons: vec![],
+    };
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let invalid = |message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: {}", number + 1, message),
+            )
+        };
+
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => {
+                let v = parse_floats(words, 3).ok_or_else(|| invalid("bad vertex"))?;
+                obj.positions.push(Vector::new(v[0], v[1], v[2]));
+            }
+            Some("vt") => {
+                let v = parse_floats(words, 2).ok_or_else(|| invalid("bad UV"))?;
+                obj.uvs.push((v[0], v[1]));
+            }
+            Some("vn") => {
+                let v = parse_floats(words, 3).ok_or_else(|| invalid("bad normal"))?;
+                obj.normals.push(Vector::new(v[0], v[1], v[2]));
+            }
+            Some("f") => {
+                let polygon = words
+                    .map(|word| {
+                        parse_corner(word, obj.positions.len(), obj.uvs.len(), obj.normals.len())
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| invalid("bad face"))?;
+                if polygon.len() < 3 {
+                    return Err(invalid("face has fewer than 3 vertices"));
+                }
+                obj.polygons.push(polygon);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(obj)
+}
+
+fn parse_floats<'a, I: Iterator<Item = &'a str>>(words: I, count: usize) -> Option<Vec<f64>> {
+    let values: Vec<f64> = words
+        .take(count)
+        .map(|word| word.parse().ok())
+        .collect::<Option<_>>()?;
+    if values.len() == count {
+        Some(values)
+    } else {
+        None
+    }
+}
+
+// Resolve a face corner such as `1`, `1/2`, `1//3` or `-1/-1/-1` into
+// zero-based position, UV and normal indices.
+fn parse_corner(word: &str, positions: usize, uvs: usize, normals: usize) -> Option<Corner> {
+    let resolve = |index: &str, count: usize| -> Option<usize> {
+        let index: i64 = index.parse().ok()?;
+        let resolved = if index < 0 {
+            count as i64 + index
+        } else {
+            index - 1
+        };
+        if resolved >= 0 && (resolved as usize) < count {
+            Some(resolved as usize)
+        } else {
+            None
+        }
+    };
+
+    let mut parts = word.split('/');
+    let position = resolve(parts.next()?, positions)?;
+    let uv = match parts.next() {
+        None | Some("") => None,
+        Some(index) => Some(resolve(index, uvs)?),
+    };
+    let normal = match parts.next() {
+        None | Some("") => None,
+        Some(index) => Some(resolve(index, normals)?),
+    };
+    Some((position, uv, normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    // Utility function to check the approximate equality of two vectors.
+    //
+    // Direct equality comparison between two vectors is unavailable due to
+    // the underlying float representation.
+    fn vec_approx_equal(v1: Vector, v2: Vector) {
+        assert_approx_eq!(v1.x, v2.x);
+        assert_approx_eq!(v1.y, v2.y);
+        assert_approx_eq!(v1.z, v2.z);
+    }
+
+    // Construction
+
+    #[test]
+    fn mesh_parse_obj() {
+        let source = "\
+# A unit square in the xy plane
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+f 1/1 2/2 3/3 4/4
+";
+        let given = Mesh::parse_obj(source.as_bytes()).unwrap();
+        assert_eq!(given.positions.len(), 4);
+        assert_eq!(given.faces, vec![[0, 1, 2], [0, 2, 3]]);
+        assert_eq!(given.uvs.as_ref().unwrap()[2], (1.0, 1.0));
+
+        // Missing normals and tangents are generated
+        for n in given.normals.unwrap() {
+            vec_approx_equal(n, Vector::new(0.0, 0.0, 1.0));
+        }
+        for t in given.tangents.unwrap() {
+            vec_approx_equal(t, Vector::new(1.0, 0.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn mesh_parse_obj_split_vertices() {
+        // The shared corner has a different normal on each face
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+v 0 0 1
+vn 0 0 1
+vn 0 1 0
+f 1//1 2//1 3//1
+f -4//2 -1//2 -3//2
+";
+        let given = Mesh::parse_obj(source.as_bytes()).unwrap();
+        assert_eq!(given.positions.len(), 6);
+        assert!(given.uvs.is_none());
+        let normals = given.normals.unwrap();
+        vec_approx_equal(normals[given.faces[0][0]], Vector::new(0.0, 0.0, 1.0));
+        vec_approx_equal(normals[given.faces[1][0]], Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn mesh_parse_obj_invalid() {
+        assert!(Mesh::parse_obj("v 0 0\n".as_bytes()).is_err());
+        assert!(Mesh::parse_obj("v 0 0 0\nf 1 2 3\n".as_bytes()).is_err());
+    }
+
+    // Attribute generation
+
+    #[test]
+    fn mesh_generate_normals() {
+        // Corner of a cube, where the top face is split into two triangles.
+        // Weighting by angle makes the extra triangle count no more than the
+        // single triangles on the other faces.
+        let positions = vec![
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(1.0, 0.0, 1.0),
+        ];
+        let faces = vec![[0, 1, 4], [0, 4, 3], [0, 3, 2], [0, 2, 1]];
+        let mut given = Mesh::new(positions, faces);
+        given.generate_normals();
+        vec_approx_equal(
+            given.normals.unwrap()[0],
+            Vector::new(-1.0, -1.0, -1.0).unit(),
+        );
+    }
+}
@@ -1,26 +1,130 @@
+use std::sync::Arc;
+
+use crate::aabb::Aabb;
+use crate::camera::Shutter;
 use crate::materials::Material;
+use crate::matrix::Matrix;
+use crate::mesh::Mesh;
 use crate::ray::Ray;
+use crate::subdivision::{Cage, Scheme};
+use crate::trajectory::Trajectory;
 use crate::vector::Vector;
 
+mod bvh;
+mod cone;
+mod csg;
+mod cuboid;
+mod curve;
+mod cylinder;
+mod disk;
+mod heightfield;
+mod instance;
+mod moving;
+mod particles;
+mod plane;
+mod rect;
+mod sdf;
 mod sphere;
+mod torus;
+mod triangle;
 
-#[derive(Clone, Copy)]
-pub struct HitRecord {
+#[derive(Clone)]
+pub struct HitRecord<'a> {
     pub t: f64,
     pub p: Vector,
     pub normal: Vector,
-    pub material: Material,
+    pub u: f64,
+    pub v: f64,
+    /// Unit direction in the surface along increasing `u` where the surface
+    /// provides one, otherwise an arbitrary direction perpendicular to the
+    /// normal.
+    pub tangent: Vector,
+    pub material: &'a Material,
 }
 
-impl HitRecord {
-    pub fn new(t: f64, p: Vector, normal: Vector, material: Material) -> HitRecord {
+impl<'a> HitRecord<'a> {
+    pub fn new(
+        t: f64,
+        p: Vector,
+        normal: Vector,
+        u: f64,
+        v: f64,
+        material: &'a Material,
+    ) -> HitRecord<'a> {
         HitRecord {
             t,
             p,
             normal,
+            u,
+            v,
+            tangent: normal.tangents().0,
             material,
         }
     }
+
+    /// Replace the tangent, making it perpendicular to the normal.
+    pub fn with_tangent(mut self, tangent: Vector) -> HitRecord<'a> {
+        let perpendicular = tangent - Vector::dot(&self.normal, &tangent) * self.normal;
+        if perpendicular.length() > 0.0 {
+            self.tangent = perpendicular.unit();
+        }
+        self
+    }
+}
+
+/// Stretch of a ray spent inside a solid object.
+#[derive(Clone)]
+pub struct Interval<'a> {
+    pub enter: HitRecord<'a>,
+    pub exit: HitRecord<'a>,
+}
+
+impl<'a> Interval<'a> {
+    pub fn new(enter: HitRecord<'a>, exit: HitRecord<'a>) -> Interval<'a> {
+        Interval { enter, exit }
+    }
+}
+
+/// Boolean operation combining two solids in constructive solid geometry.
+#[derive(Copy, Clone, Debug)]
+pub enum Operation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Cross-section of a curve.
+#[derive(Copy, Clone, Debug)]
+pub enum CurveShape {
+    /// Flat strip which always faces the ray.
+    Flat,
+    /// Flat strip facing the ray, shaded as if it were a cylinder. Suits
+    /// thin fibres such as hair and fur.
+    Cylinder,
+    /// Strip facing along normals interpolated from the start to the end of
+    /// the curve, such as a blade of grass.
+    Ribbon {
+        start_normal: Vector,
+        end_normal: Vector,
+    },
+}
+
+/// Coordinate axis, used to orient axis-aligned objects.
+#[derive(Copy, Clone, Debug)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    pub fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
 }
 
 pub enum Object {
@@ -29,7 +133,86 @@ pub enum Object {
         radius: f64,
         material: Material,
     },
+    Plane {
+        point: Vector,
+        normal: Vector,
+        material: Material,
+    },
+    Disk {
+        center: Vector,
+        normal: Vector,
+        radius: f64,
+        material: Material,
+    },
+    Rect {
+        axis: Axis,
+        a0: f64,
+        a1: f64,
+        b0: f64,
+        b1: f64,
+        k: f64,
+        flip: bool,
+        material: Material,
+    },
+    Cuboid {
+        min: Vector,
+        max: Vector,
+        material: Material,
+    },
+    Cylinder {
+        center: Vector,
+        radius: f64,
+        height: f64,
+        material: Material,
+    },
+    Cone {
+        center: Vector,
+        radius: f64,
+        height: f64,
+        material: Material,
+    },
+    Torus {
+        center: Vector,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Material,
+    },
+    Sdf {
+        distance: Arc<dyn Fn(Vector) -> f64 + Send + Sync>,
+        bounds: Aabb,
+        epsilon: f64,
+        max_steps: u32,
+        material: Material,
+    },
+    Curve {
+        control_points: [Vector; 4],
+        widths: (f64, f64),
+        shape: CurveShape,
+        material: Material,
+    },
+    Heightfield(heightfield::Heightfield),
+    Particles(particles::Particles),
+    Triangle {
+        mesh: Arc<Mesh>,
+        index: usize,
+        material: Arc<Material>,
+    },
+    Csg {
+        operation: Operation,
+        left: Box<Object>,
+        right: Box<Object>,
+    },
     Multiple(Vec<Object>),
+    Moving {
+        trajectory: Trajectory,
+        object: Box<Object>,
+    },
+    Instance {
+        transform: Matrix,
+        inverse: Matrix,
+        object: Arc<Object>,
+    },
+    Bvh(bvh::Bvh),
 }
 
 impl Object {
@@ -41,25 +224,596 @@ impl Object {
         }
     }
 
-    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    /// Infinite plane through `point`.
+    pub fn new_plane(point: Vector, normal: Vector, material: Material) -> Object {
+        Object::Plane {
+            point,
+            normal: normal.unit(),
+            material,
+        }
+    }
+
+    pub fn new_disk(center: Vector, normal: Vector, radius: f64, material: Material) -> Object {
+        Object::Disk {
+            center,
+            normal: normal.unit(),
+            radius,
+            material,
+        }
+    }
+
+    /// Rectangle perpendicular to `axis` at `k` along it. The remaining two
+    /// axes, taken in cyclic order after `axis`, span `[a0, a1]` and
+    /// `[b0, b1]`. The normal points along the positive `axis`, or the
+    /// negative one if `flip` is set, such as for a light facing down.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_rect(
+        axis: Axis,
+        a0: f64,
+        a1: f64,
+        b0: f64,
+        b1: f64,
+        k: f64,
+        flip: bool,
+        material: Material,
+    ) -> Object {
+        Object::Rect {
+            axis,
+            a0,
+            a1,
+            b0,
+            b1,
+            k,
+            flip,
+            material,
+        }
+    }
+
+    /// Axis-aligned box spanning `min` to `max`.
+    pub fn new_cuboid(min: Vector, max: Vector, material: Material) -> Object {
+        Object::Cuboid { min, max, material }
+    }
+
+    /// Box centred on `center` and turned by `rotation`, extending
+    /// `half_extents` along each of its own axes.
+    pub fn new_oriented_box(
+        center: Vector,
+        half_extents: Vector,
+        rotation: Matrix,
+        material: Material,
+    ) -> Object {
+        Object::new_instance(
+            Arc::new(Object::new_cuboid(-half_extents, half_extents, material)),
+            Matrix::translation(center) * rotation,
+        )
+    }
+
+    /// Capped cylinder standing upright along the y axis, with the centre of
+    /// its base at `center`. Other orientations are available by instancing.
+    pub fn new_cylinder(center: Vector, radius: f64, height: f64, material: Material) -> Object {
+        Object::Cylinder {
+            center,
+            radius,
+            height,
+            material,
+        }
+    }
+
+    /// Cone standing upright along the y axis, with the centre of its base at
+    /// `center` and its apex `height` above. Other orientations are available
+    /// by instancing.
+    pub fn new_cone(center: Vector, radius: f64, height: f64, material: Material) -> Object {
+        Object::Cone {
+            center,
+            radius,
+            height,
+            material,
+        }
+    }
+
+    /// Torus lying in the xz plane around `center`, where `major_radius` is
+    /// the distance to the centre of the tube and `minor_radius` the radius
+    /// of the tube. Other orientations are available by instancing.
+    pub fn new_torus(
+        center: Vector,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Material,
+    ) -> Object {
+        Object::Torus {
+            center,
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+
+    /// Implicit surface where the signed `distance` function is zero, found
+    /// by sphere tracing within `bounds`. Marching stops within `epsilon` of
+    /// the surface or after `max_steps` steps.
+    ///
+    /// Implicit shapes are combined through their distance functions (see
+    /// the `distance` module) rather than by constructive solid geometry.
+    pub fn new_sdf<F>(
+        distance: F,
+        bounds: Aabb,
+        epsilon: f64,
+        max_steps: u32,
+        material: Material,
+    ) -> Object
+    where
+        F: Fn(Vector) -> f64 + Send + Sync + 'static,
+    {
+        Object::Sdf {
+            distance: Arc::new(distance),
+            bounds,
+            epsilon,
+            max_steps,
+            material,
+        }
+    }
+
+    /// Cubic Bézier curve through the first and last of the
+    /// `control_points`, whose width varies linearly from `width0` to
+    /// `width1` along it. Hit records carry the position along the curve as
+    /// `u`, the position across it as `v` and the curve's direction as the
+    /// tangent.
+    pub fn new_curve(
+        control_points: [Vector; 4],
+        width0: f64,
+        width1: f64,
+        shape: CurveShape,
+        material: Material,
+    ) -> Object {
+        let shape = match shape {
+            CurveShape::Ribbon {
+                start_normal,
+                end_normal,
+            } => CurveShape::Ribbon {
+                start_normal: start_normal.unit(),
+                end_normal: end_normal.unit(),
+            },
+            shape => shape,
+        };
+        Object::Curve {
+            control_points,
+            widths: (width0, width1),
+            shape,
+            material,
+        }
+    }
+
+    /// Set of spherical particles given as flat arrays, each with its own
+    /// radius and a colour which tints the shared `material`. Particles are
+    /// held in their own bounding volume hierarchy, which makes large sets
+    /// far cheaper than individual spheres.
+    ///
+    /// Materials without an albedo to tint, such as dielectrics, are
+    /// rejected rather than ignoring the colours.
+    pub fn new_particles(
+        positions: Vec<Vector>,
+        radii: Vec<f64>,
+        colours: Vec<Vector>,
+        material: Material,
+    ) -> Object {
+        Object::Particles(particles::Particles::new(
+            positions, radii, colours, material,
+        ))
+    }
+
+    /// Terrain from a grid of `heights` in row-major order, with columns
+    /// running along x and rows along z. The grid spans `size.x` by `size.z`
+    /// from `corner`, and each height is scaled by `size.y` and raised by
+    /// `corner.y`.
+    pub fn new_heightfield(
+        heights: Vec<f64>,
+        columns: usize,
+        corner: Vector,
+        size: Vector,
+        material: Material,
+    ) -> Object {
+        Object::Heightfield(heightfield::Heightfield::new(
+            heights, columns, corner, size, material,
+        ))
+    }
+
+    /// Terrain from a grayscale image, such as `image::open(path)?.to_luma()`,
+    /// where black is at `corner.y` and white is `size.y` above it. Pixel
+    /// columns run along x and rows along z.
+    pub fn new_heightfield_from_image(
+        image: &image::GrayImage,
+        corner: Vector,
+        size: Vector,
+        material: Material,
+    ) -> Object {
+        let heights = image
+            .pixels()
+            .map(|pixel| f64::from(pixel.data[0]) / 255.0)
+            .collect();
+        Object::new_heightfield(heights, image.width() as usize, corner, size, material)
+    }
+
+    /// Face `index` of a shared `mesh`, whose `material` is shared with the
+    /// other faces.
+    pub fn new_triangle(mesh: Arc<Mesh>, index: usize, material: Arc<Material>) -> Object {
+        Object::Triangle {
+            mesh,
+            index,
+            material,
+        }
+    }
+
+    /// Every face of `mesh`, held in a bounding volume hierarchy. The mesh
+    /// is static, so is bounded at time 0 only; wrap it in a moving object
+    /// to move it.
+    pub fn new_mesh(mesh: Mesh, material: Material) -> Object {
+        let mesh = Arc::new(mesh);
+        let material = Arc::new(material);
+        let triangles = (0..mesh.faces.len())
+            .map(|index| Object::new_triangle(mesh.clone(), index, material.clone()))
+            .collect();
+        Object::new_bvh(triangles, Shutter::new(0.0, 0.0))
+    }
+
+    /// Smooth surface refined from a control `cage` by `levels` of
+    /// subdivision under the given `scheme`.
+    pub fn new_subdivision(cage: &Cage, scheme: Scheme, levels: u32, material: Material) -> Object {
+        Object::new_mesh(cage.subdivide(scheme, levels), material)
+    }
+
+    /// Solid combining two solid objects by a boolean `operation`. Both
+    /// operands must be solid, as surfaces have no inside to combine.
+    pub fn new_csg(operation: Operation, left: Object, right: Object) -> Object {
+        assert!(
+            left.is_solid() && right.is_solid(),
+            "CSG requires solid operands!"
+        );
+        Object::Csg {
+            operation,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    /// Solid inside either `left` or `right`.
+    pub fn new_union(left: Object, right: Object) -> Object {
+        Object::new_csg(Operation::Union, left, right)
+    }
+
+    /// Solid inside both `left` and `right`.
+    pub fn new_intersection(left: Object, right: Object) -> Object {
+        Object::new_csg(Operation::Intersection, left, right)
+    }
+
+    /// Solid inside `left` but not `right`.
+    pub fn new_difference(left: Object, right: Object) -> Object {
+        Object::new_csg(Operation::Difference, left, right)
+    }
+
+    /// Displace `object` over time along `trajectory`.
+    pub fn new_moving(trajectory: Trajectory, object: Object) -> Object {
+        Object::Moving {
+            trajectory,
+            object: Box::new(object),
+        }
+    }
+
+    /// Sphere moving with constant velocity from `center0` at `time0` to
+    /// `center1` at `time1`.
+    pub fn new_moving_sphere(
+        center0: Vector,
+        center1: Vector,
+        time0: f64,
+        time1: f64,
+        radius: f64,
+        material: Material,
+    ) -> Object {
+        Object::new_moving(
+            Trajectory::linear(time0, Vector::new(0.0, 0.0, 0.0), time1, center1 - center0),
+            Object::new_sphere(center0, radius, material),
+        )
+    }
+
+    /// Place a shared `object` in the world by an affine `transform`.
+    ///
+    /// Panics if the transform cannot be inverted.
+    pub fn new_instance(object: Arc<Object>, transform: Matrix) -> Object {
+        let inverse = transform
+            .inverse()
+            .expect("Instance transform is not invertible!");
+        Object::Instance {
+            transform,
+            inverse,
+            object,
+        }
+    }
+
+    /// Bounding volume hierarchy over `objects`, which may move while the
+    /// camera `shutter` is open. Moving objects are only bounded over that
+    /// time, so the shutter must be the one of the camera rendering them.
+    ///
+    /// A hierarchy over instances whose shared objects are themselves
+    /// hierarchies forms a two-level structure, where each piece of unique
+    /// geometry is stored once and rays are only transformed into an
+    /// instance's space after hitting its bounds.
+    pub fn new_bvh(objects: Vec<Object>, shutter: Shutter) -> Object {
+        Object::Bvh(bvh::Bvh::new(objects, shutter.open, shutter.close))
+    }
+
+    pub fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
+        match self {
+            Object::Sphere {
+                center,
+                radius,
+                material,
+            } => sphere::hit(*center, *radius, material, r, t_min, t_max),
+            Object::Plane {
+                point,
+                normal,
+                material,
+            } => plane::hit(*point, *normal, material, r, t_min, t_max),
+            Object::Disk {
+                center,
+                normal,
+                radius,
+                material,
+            } => disk::hit(*center, *normal, *radius, material, r, t_min, t_max),
+            Object::Rect {
+                axis,
+                a0,
+                a1,
+                b0,
+                b1,
+                k,
+                flip,
+                material,
+            } => rect::hit(
+                *axis,
+                (*a0, *a1),
+                (*b0, *b1),
+                *k,
+                *flip,
+                material,
+                r,
+                t_min,
+                t_max,
+            ),
+            Object::Cuboid { min, max, material } => {
+                cuboid::hit(*min, *max, material, r, t_min, t_max)
+            }
+            Object::Cylinder {
+                center,
+                radius,
+                height,
+                material,
+            } => cylinder::hit(*center, *radius, *height, material, r, t_min, t_max),
+            Object::Cone {
+                center,
+                radius,
+                height,
+                material,
+            } => cone::hit(*center, *radius, *height, material, r, t_min, t_max),
+            Object::Torus {
+                center,
+                major_radius,
+                minor_radius,
+                material,
+            } => torus::hit(
+                *center,
+                *major_radius,
+                *minor_radius,
+                material,
+                r,
+                t_min,
+                t_max,
+            ),
+            Object::Sdf {
+                distance,
+                bounds,
+                epsilon,
+                max_steps,
+                material,
+            } => sdf::hit(
+                distance.as_ref(),
+                bounds,
+                *epsilon,
+                *max_steps,
+                material,
+                r,
+                t_min,
+                t_max,
+            ),
+            Object::Curve {
+                control_points,
+                widths,
+                shape,
+                material,
+            } => curve::hit(control_points, *widths, *shape, material, r, t_min, t_max),
+            Object::Heightfield(heightfield) => heightfield::hit(heightfield, r, t_min, t_max),
+            Object::Particles(particles) => particles::hit(particles, r, t_min, t_max),
+            Object::Triangle {
+                mesh,
+                index,
+                material,
+            } => triangle::hit(mesh, *index, material, r, t_min, t_max),
+            Object::Csg {
+                operation,
+                left,
+                right,
+            } => csg::hit(*operation, left, right, r, t_min, t_max),
+            Object::Multiple(objects) => hit(objects, r, t_min, t_max),
+            Object::Moving { trajectory, object } => {
+                moving::hit(trajectory, object, r, t_min, t_max)
+            }
+            Object::Instance {
+                transform,
+                inverse,
+                object,
+            } => instance::hit(transform, inverse, object, r, t_min, t_max),
+            Object::Bvh(bvh) => bvh::hit(bvh, r, t_min, t_max),
+        }
+    }
+
+    /// Box containing the object at all times between `time0` and `time1`,
+    /// or `None` if the object is unbounded.
+    pub fn bounding_box(&self, time0: f64, time1: f64) -> Option<Aabb> {
+        match self {
+            Object::Sphere { center, radius, .. } => Some(sphere::bounding_box(*center, *radius)),
+            Object::Plane { .. } => None,
+            Object::Disk {
+                center,
+                normal,
+                radius,
+                ..
+            } => Some(disk::bounding_box(*center, *normal, *radius)),
+            Object::Rect {
+                axis,
+                a0,
+                a1,
+                b0,
+                b1,
+                k,
+                ..
+            } => Some(rect::bounding_box(*axis, (*a0, *a1), (*b0, *b1), *k)),
+            Object::Cuboid { min, max, .. } => Some(cuboid::bounding_box(*min, *max)),
+            Object::Cylinder {
+                center,
+                radius,
+                height,
+                ..
+            } => Some(cylinder::bounding_box(*center, *radius, *height)),
+            Object::Cone {
+                center,
+                radius,
+                height,
+                ..
+            } => Some(cone::bounding_box(*center, *radius, *height)),
+            Object::Torus {
+                center,
+                major_radius,
+                minor_radius,
+                ..
+            } => Some(torus::bounding_box(*center, *major_radius, *minor_radius)),
+            Object::Sdf { bounds, .. } => Some(*bounds),
+            Object::Curve {
+                control_points,
+                widths,
+                ..
+            } => Some(curve::bounding_box(control_points, *widths)),
+            Object::Heightfield(heightfield) => Some(heightfield.bounding_box()),
+            Object::Particles(particles) => particles.bounding_box(),
+            Object::Triangle { mesh, index, .. } => Some(triangle::bounding_box(mesh, *index)),
+            Object::Csg {
+                operation,
+                left,
+                right,
+            } => csg::bounding_box(*operation, left, right, time0, time1),
+            Object::Multiple(objects) => bounding_box(objects, time0, time1),
+            Object::Moving { trajectory, object } => {
+                moving::bounding_box(trajectory, object, time0, time1)
+            }
+            Object::Instance {
+                transform, object, ..
+            } => object
+                .bounding_box(time0, time1)
+                .map(|bounds| bounds.transform(transform)),
+            Object::Bvh(bvh) => bvh.bounding_box(),
+        }
+    }
+
+    /// Whether the object encloses a volume whose `intervals` are known:
+    /// spheres, boxes, cylinders, cones, tori, and solids built from them.
+    pub fn is_solid(&self) -> bool {
+        match self {
+            Object::Sphere { .. }
+            | Object::Cuboid { .. }
+            | Object::Cylinder { .. }
+            | Object::Cone { .. }
+            | Object::Torus { .. }
+            | Object::Csg { .. } => true,
+            Object::Moving { object, .. } => object.is_solid(),
+            Object::Instance { object, .. } => object.is_solid(),
+            Object::Plane { .. }
+            | Object::Disk { .. }
+            | Object::Rect { .. }
+            | Object::Sdf { .. }
+            | Object::Curve { .. }
+            | Object::Heightfield(_)
+            | Object::Particles(_)
+            | Object::Triangle { .. }
+            | Object::Multiple(_)
+            | Object::Bvh(_) => false,
+        }
+    }
+
+    /// Every interval, at any distance along the ray, which the ray spends
+    /// inside the object. Objects that are not solid have none.
+    pub fn intervals(&self, r: &Ray) -> Vec<Interval<'_>> {
         match self {
             Object::Sphere {
                 center,
                 radius,
                 material,
-            } => sphere::hit(*center, *radius, *material, r, t_min, t_max),
-            Object::Multiple(objects) => hit(&objects, r, t_min, t_max),
+            } => pair(sphere::crossings(*center, *radius, material, r)),
+            Object::Cuboid { min, max, material } => {
+                pair(cuboid::crossings(*min, *max, material, r))
+            }
+            Object::Cylinder {
+                center,
+                radius,
+                height,
+                material,
+            } => pair(cylinder::crossings(*center, *radius, *height, material, r)),
+            Object::Cone {
+                center,
+                radius,
+                height,
+                material,
+            } => pair(cone::crossings(*center, *radius, *height, material, r)),
+            Object::Torus {
+                center,
+                major_radius,
+                minor_radius,
+                material,
+            } => pair(torus::crossings(
+                *center,
+                *major_radius,
+                *minor_radius,
+                material,
+                r,
+            )),
+            Object::Csg {
+                operation,
+                left,
+                right,
+            } => csg::intervals(*operation, left, right, r),
+            Object::Moving { trajectory, object } => moving::intervals(trajectory, object, r),
+            Object::Instance {
+                transform,
+                inverse,
+                object,
+            } => instance::intervals(transform, inverse, object, r),
+            Object::Plane { .. }
+            | Object::Disk { .. }
+            | Object::Rect { .. }
+            | Object::Sdf { .. }
+            | Object::Curve { .. }
+            | Object::Heightfield(_)
+            | Object::Particles(_)
+            | Object::Triangle { .. }
+            | Object::Multiple(_)
+            | Object::Bvh(_) => vec![],
         }
     }
 }
 
-fn hit(objects: &Vec<Object>, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+fn hit<'a>(objects: &'a [Object], r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
     let mut closest_hit: Option<HitRecord> = None;
     for hitable in objects.iter() {
         if let Some(hit) = hitable.hit(r, t_min, t_max) {
             match closest_hit {
                 None => closest_hit = Some(hit),
-                Some(prev_hit) => {
+                Some(ref prev_hit) => {
                     if hit.t < prev_hit.t {
                         closest_hit = Some(hit)
                     }
@@ -69,3 +823,781 @@ fn hit(objects: &Vec<Object>, r: &Ray, t_min: f64, t_max: f64) -> Option<HitReco
     }
     closest_hit
 }
+
+// Nearest of the `candidates` crossings strictly between `t_min` and
+// `t_max`, each given by its parameter along the ray and whatever else is
+// needed to build its record.
+fn nearest_in_range<T>(
+    candidates: impl Iterator<Item = (f64, T)>,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, T)> {
+    candidates
+        .filter(|&(t, _)| t > t_min && t < t_max)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+}
+
+// Intervals between alternate crossings of a closed surface.
+fn pair(crossings: Vec<HitRecord<'_>>) -> Vec<Interval<'_>> {
+    let mut crossings = crossings.into_iter();
+    let mut intervals = vec![];
+    while let (Some(enter), Some(exit)) = (crossings.next(), crossings.next()) {
+        intervals.push(Interval::new(enter, exit));
+    }
+    intervals
+}
+
+fn bounding_box(objects: &[Object], time0: f64, time1: f64) -> Option<Aabb> {
+    let mut bounds: Option<Aabb> = None;
+    for object in objects.iter() {
+        let object_bounds = object.bounding_box(time0, time1)?;
+        bounds = match bounds {
+            None => Some(object_bounds),
+            Some(prev_bounds) => Some(Aabb::surrounding(&prev_bounds, &object_bounds)),
+        }
+    }
+    bounds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    // Utility function to check the approximate equality of two vectors.
+    //
+    // Direct equality comparison between two vectors is unavailable due to
+    // the underlying float representation.
+    fn vec_approx_equal(v1: Vector, v2: Vector) {
+        assert_approx_eq!(v1.x, v2.x);
+        assert_approx_eq!(v1.y, v2.y);
+        assert_approx_eq!(v1.z, v2.z);
+    }
+
+    fn material() -> Material {
+        Material::new_lambertian(Vector::new(0.5, 0.5, 0.5))
+    }
+
+    #[test]
+    fn object_moving_hit() {
+        let sphere = Object::new_moving_sphere(
+            Vector::new(0.0, 0.0, -5.0),
+            Vector::new(2.0, 0.0, -5.0),
+            0.0,
+            1.0,
+            0.5,
+            material(),
+        );
+        let r = Ray::new(Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(sphere.hit(&r, 0.0001, f64::MAX).is_none());
+
+        let r = Ray::new(Vector::new(1.0, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0), 0.5);
+        let hit = sphere.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.p, Vector::new(1.0, 0.0, -4.5));
+    }
+
+    #[test]
+    fn object_instance_hit() {
+        let sphere = Arc::new(Object::new_sphere(
+            Vector::new(0.0, 0.0, 0.0),
+            1.0,
+            material(),
+        ));
+        let transform = Matrix::translation(Vector::new(0.0, 0.0, -5.0))
+            * Matrix::scaling(Vector::new(2.0, 1.0, 1.0));
+        let instance = Object::new_instance(sphere, transform);
+
+        // Hit the stretched side of the sphere
+        let r = Ray::new(
+            Vector::new(5.0, 0.0, -5.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            0.0,
+        );
+        let hit = instance.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 3.0);
+        vec_approx_equal(hit.p, Vector::new(2.0, 0.0, -5.0));
+        vec_approx_equal(hit.normal, Vector::new(1.0, 0.0, 0.0));
+
+        // The normal of an ellipsoid is not its radial direction
+        let p = Vector::new(2.0_f64.sqrt(), 0.5_f64.sqrt(), -5.0);
+        let n = Vector::new(0.5_f64.sqrt() / 2.0, 0.5_f64.sqrt(), 0.0).unit();
+        let r = Ray::new(p + n, -n, 0.0);
+        let hit = instance.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.p, p);
+        vec_approx_equal(hit.normal, n);
+    }
+
+    #[test]
+    fn object_bvh_matches_list() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(31);
+        let spheres: Vec<(Vector, f64)> = (0..50)
+            .map(|_| {
+                let center = Vector::new(
+                    rng.gen_range(-10.0, 10.0),
+                    rng.gen_range(-10.0, 10.0),
+                    rng.gen_range(-10.0, 10.0),
+                );
+                (center, rng.gen_range(0.1, 2.0))
+            })
+            .collect();
+        let objects = || {
+            spheres
+                .iter()
+                .map(|&(center, radius)| Object::new_sphere(center, radius, material()))
+                .collect()
+        };
+        let list = Object::Multiple(objects());
+        let bvh = Object::new_bvh(objects(), Shutter::new(0.0, 0.0));
+
+        for _ in 0..200 {
+            let r = Ray::new(
+                Vector::new(0.0, 0.0, 20.0),
+                Vector::new(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), -1.0),
+                0.0,
+            );
+            match (
+                list.hit(&r, 0.0001, f64::MAX),
+                bvh.hit(&r, 0.0001, f64::MAX),
+            ) {
+                (None, None) => {}
+                (Some(expected), Some(given)) => assert_approx_eq!(given.t, expected.t),
+                _ => panic!("BVH and list disagree!"),
+            }
+        }
+    }
+
+    #[test]
+    fn object_bvh_moving() {
+        // Moving objects stay in the hierarchy for as long as the shutter
+        let sphere = Object::new_moving_sphere(
+            Vector::new(0.0, 0.0, -5.0),
+            Vector::new(4.0, 0.0, -5.0),
+            0.0,
+            1.0,
+            0.5,
+            material(),
+        );
+        let bvh = Object::new_bvh(vec![sphere], Shutter::new(0.0, 1.0));
+        let r = Ray::new(Vector::new(4.0, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0), 1.0);
+        let hit = bvh.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.p, Vector::new(4.0, 0.0, -4.5));
+    }
+
+    #[test]
+    fn object_bvh_of_instances() {
+        let shared = Arc::new(Object::new_bvh(
+            vec![
+                Object::new_sphere(Vector::new(0.0, 0.0, 0.0), 1.0, material()),
+                Object::new_sphere(Vector::new(0.0, 2.0, 0.0), 0.5, material()),
+            ],
+            Shutter::new(0.0, 0.0),
+        ));
+        let instances = (0..10)
+            .map(|i| {
+                Object::new_instance(
+                    shared.clone(),
+                    Matrix::translation(Vector::new(5.0 * f64::from(i), 0.0, 0.0)),
+                )
+            })
+            .collect();
+        let world = Object::new_bvh(instances, Shutter::new(0.0, 0.0));
+
+        let bounds = world.bounding_box(0.0, 0.0).unwrap();
+        vec_approx_equal(bounds.min, Vector::new(-1.0, -1.0, -1.0));
+        vec_approx_equal(bounds.max, Vector::new(46.0, 2.5, 1.0));
+
+        let r = Ray::new(
+            Vector::new(35.0, 2.0, 5.0),
+            Vector::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let hit = world.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.p, Vector::new(35.0, 2.0, 0.5));
+
+        let r = Ray::new(
+            Vector::new(37.5, 0.0, 5.0),
+            Vector::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        assert!(world.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_plane_hit() {
+        let plane = Object::new_plane(
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 2.0, 0.0),
+            material(),
+        );
+        let r = Ray::new(Vector::new(1.0, 3.0, 2.0), Vector::new(0.0, -1.0, 0.0), 0.0);
+        let hit = plane.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 2.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 1.0, 0.0));
+        assert_approx_eq!(hit.u * hit.u + hit.v * hit.v, 5.0);
+        assert!(plane.bounding_box(0.0, 0.0).is_none());
+
+        let r = Ray::new(Vector::new(1.0, 3.0, 2.0), Vector::new(1.0, 0.0, 0.0), 0.0);
+        assert!(plane.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_disk_hit() {
+        let disk = Object::new_disk(
+            Vector::new(0.0, 0.0, -2.0),
+            Vector::new(0.0, 0.0, 1.0),
+            1.0,
+            material(),
+        );
+        let r = Ray::new(Vector::new(0.5, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let hit = disk.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 2.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+        assert_approx_eq!(hit.v, 0.5);
+
+        let r = Ray::new(Vector::new(1.5, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(disk.hit(&r, 0.0001, f64::MAX).is_none());
+
+        let bounds = disk.bounding_box(0.0, 0.0).unwrap();
+        assert_approx_eq!(bounds.max.x, 1.0, 0.001);
+        assert_approx_eq!(bounds.max.z, -2.0, 0.001);
+    }
+
+    #[test]
+    fn object_rect_hit() {
+        let rect = Object::new_rect(Axis::Y, 0.0, 2.0, 0.0, 4.0, 1.0, false, material());
+        let r = Ray::new(Vector::new(1.0, 3.0, 0.5), Vector::new(0.0, -1.0, 0.0), 0.0);
+        let hit = rect.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 2.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 1.0, 0.0));
+        assert_approx_eq!(hit.u, 0.25);
+        assert_approx_eq!(hit.v, 0.25);
+
+        let r = Ray::new(Vector::new(5.0, 3.0, 0.5), Vector::new(0.0, -1.0, 0.0), 0.0);
+        assert!(rect.hit(&r, 0.0001, f64::MAX).is_none());
+
+        // Flipped to face down
+        let rect = Object::new_rect(Axis::Y, 0.0, 2.0, 0.0, 4.0, 1.0, true, material());
+        let r = Ray::new(Vector::new(1.0, 3.0, 0.5), Vector::new(0.0, -1.0, 0.0), 0.0);
+        let hit = rect.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.normal, Vector::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn object_cuboid_hit() {
+        let cuboid = Object::new_cuboid(
+            Vector::new(-1.0, -1.0, -1.0),
+            Vector::new(1.0, 2.0, 1.0),
+            material(),
+        );
+        let r = Ray::new(Vector::new(0.0, 0.5, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let hit = cuboid.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+        assert_approx_eq!(hit.u, 0.5);
+        assert_approx_eq!(hit.v, 0.5);
+
+        // From the inside the far face is hit
+        let r = Ray::new(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0), 0.0);
+        let hit = cuboid.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 2.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn object_oriented_box_hit() {
+        let cuboid = Object::new_oriented_box(
+            Vector::new(0.0, 0.0, -5.0),
+            Vector::new(1.0, 1.0, 1.0),
+            Matrix::rotation(Vector::new(0.0, 1.0, 0.0), 45.0),
+            material(),
+        );
+        let r = Ray::new(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let hit = cuboid.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 5.0 - 2.0_f64.sqrt());
+        vec_approx_equal(hit.normal, Vector::new(1.0, 0.0, 1.0).unit());
+    }
+
+    #[test]
+    fn object_cylinder_hit() {
+        let cylinder = Object::new_cylinder(Vector::new(0.0, -1.0, 0.0), 1.0, 2.0, material());
+        let r = Ray::new(Vector::new(5.0, 0.0, 0.0), Vector::new(-1.0, 0.0, 0.0), 0.0);
+        let hit = cylinder.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.0);
+        vec_approx_equal(hit.normal, Vector::new(1.0, 0.0, 0.0));
+        assert_approx_eq!(hit.v, 0.5);
+
+        let r = Ray::new(Vector::new(0.5, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0), 0.0);
+        let hit = cylinder.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 1.0, 0.0));
+
+        let r = Ray::new(Vector::new(5.0, 1.5, 0.0), Vector::new(-1.0, 0.0, 0.0), 0.0);
+        assert!(cylinder.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_cone_hit() {
+        let cone = Object::new_cone(Vector::new(0.0, 0.0, 0.0), 1.0, 1.0, material());
+        let r = Ray::new(Vector::new(5.0, 0.5, 0.0), Vector::new(-1.0, 0.0, 0.0), 0.0);
+        let hit = cone.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.5);
+        vec_approx_equal(hit.normal, Vector::new(1.0, 1.0, 0.0).unit());
+
+        let r = Ray::new(Vector::new(0.5, -5.0, 0.0), Vector::new(0.0, 1.0, 0.0), 0.0);
+        let hit = cone.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 5.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, -1.0, 0.0));
+
+        // The mirrored nappe above the apex is not part of the cone
+        let r = Ray::new(Vector::new(5.0, 1.5, 0.0), Vector::new(-1.0, 0.0, 0.0), 0.0);
+        assert!(cone.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_torus_hit() {
+        let torus = Object::new_torus(Vector::new(0.0, 0.0, -10.0), 2.0, 0.5, material());
+        let r = Ray::new(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, -2.0), 0.0);
+        let hit = torus.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 7.5 / 2.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+
+        // Through the hole
+        let r = Ray::new(
+            Vector::new(0.0, 5.0, -10.0),
+            Vector::new(0.0, -1.0, 0.0),
+            0.0,
+        );
+        assert!(torus.hit(&r, 0.0001, f64::MAX).is_none());
+
+        // Down onto the top of the tube
+        let r = Ray::new(
+            Vector::new(2.0, 5.0, -10.0),
+            Vector::new(0.0, -1.0, 0.0),
+            0.0,
+        );
+        let hit = torus.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.5);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn object_csg_intersection_lens() {
+        let lens = Object::new_intersection(
+            Object::new_sphere(Vector::new(0.0, 0.0, -1.5), 2.0, material()),
+            Object::new_sphere(Vector::new(0.0, 0.0, 1.5), 2.0, material()),
+        );
+        let r = Ray::new(Vector::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let intervals = lens.intervals(&r);
+        assert_eq!(intervals.len(), 1);
+        assert_approx_eq!(intervals[0].enter.t, 4.5);
+        assert_approx_eq!(intervals[0].exit.t, 5.5);
+
+        let hit = lens.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.5);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+
+        // Inside only one of the spheres
+        let r = Ray::new(Vector::new(0.0, 1.8, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(lens.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_csg_difference() {
+        let holed = Object::new_difference(
+            Object::new_cuboid(
+                Vector::new(-1.0, -1.0, -1.0),
+                Vector::new(1.0, 1.0, 1.0),
+                material(),
+            ),
+            Object::new_sphere(Vector::new(0.0, 0.0, 1.0), 0.5, material()),
+        );
+
+        // Through the hole onto its spherical floor, which faces outwards
+        let r = Ray::new(Vector::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let hit = holed.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.5);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+
+        // Beside the hole
+        let r = Ray::new(Vector::new(0.8, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let hit = holed.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.0);
+    }
+
+    #[test]
+    fn object_csg_union() {
+        let union = Object::new_union(
+            Object::new_sphere(Vector::new(0.0, 0.0, -1.0), 1.5, material()),
+            Object::new_sphere(Vector::new(0.0, 0.0, 1.0), 1.5, material()),
+        );
+        let r = Ray::new(Vector::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let intervals = union.intervals(&r);
+        assert_eq!(intervals.len(), 1);
+        assert_approx_eq!(intervals[0].enter.t, 2.5);
+        assert_approx_eq!(intervals[0].exit.t, 7.5);
+
+        let bounds = union.bounding_box(0.0, 0.0).unwrap();
+        vec_approx_equal(bounds.min, Vector::new(-1.5, -1.5, -2.5));
+        vec_approx_equal(bounds.max, Vector::new(1.5, 1.5, 2.5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn object_csg_surface() {
+        let _ = Object::new_difference(
+            Object::new_sphere(Vector::new(0.0, 0.0, 0.0), 1.0, material()),
+            Object::new_plane(
+                Vector::new(0.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+                material(),
+            ),
+        );
+    }
+
+    #[test]
+    fn object_sdf_hit() {
+        let center = Vector::new(0.0, 0.0, -5.0);
+        let sdf = Object::new_sdf(
+            move |p| crate::distance::sphere(p - center, 1.0),
+            Aabb::new(Vector::new(-1.0, -1.0, -6.0), Vector::new(1.0, 1.0, -4.0)),
+            1e-6,
+            100,
+            material(),
+        );
+        let r = Ray::new(Vector::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, -2.0), 0.0);
+        let hit = sdf.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 2.0, 1e-5);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+
+        // Leaving the surface from the inside finds the far side
+        let r = Ray::new(hit.p, Vector::new(0.0, 0.0, -1.0), 0.0);
+        let hit = sdf.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 2.0, 1e-5);
+
+        let r = Ray::new(Vector::new(0.0, 2.0, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(sdf.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_heightfield_hit() {
+        // A ridge along z, rising from 0 at x = 0 to 2 at x = 2 and back
+        let heights = vec![0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0];
+        let heightfield = Object::new_heightfield(
+            heights,
+            3,
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(4.0, 2.0, 4.0),
+            material(),
+        );
+
+        let r = Ray::new(Vector::new(1.0, 5.0, 1.0), Vector::new(0.0, -1.0, 0.0), 0.0);
+        let hit = heightfield.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 4.0);
+        assert_approx_eq!(hit.u, 0.25);
+        assert_approx_eq!(hit.v, 0.25);
+        // Halfway between the slope's edge and the crest, whose normal is
+        // interpolated from those of both slopes
+        let expected = (Vector::new(-1.0, 1.0, 0.0).unit() + Vector::new(0.0, 1.0, 0.0)).unit();
+        vec_approx_equal(hit.normal, expected);
+
+        // Along the crest itself the normal points straight up
+        let r = Ray::new(Vector::new(2.0, 5.0, 3.0), Vector::new(0.0, -1.0, 0.0), 0.0);
+        let hit = heightfield.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 3.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 1.0, 0.0));
+
+        // Skim along the valley from beyond the grid into the far slope
+        let r = Ray::new(Vector::new(-4.0, 0.5, 3.0), Vector::new(1.0, 0.0, 0.0), 0.0);
+        let hit = heightfield.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.p, Vector::new(0.5, 0.5, 3.0));
+        let r = Ray::new(Vector::new(8.0, 0.5, 3.0), Vector::new(-1.0, 0.0, 0.0), 0.0);
+        let hit = heightfield.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.p, Vector::new(3.5, 0.5, 3.0));
+
+        // Pass over the crest
+        let r = Ray::new(Vector::new(-4.0, 2.5, 3.0), Vector::new(1.0, 0.0, 0.0), 0.0);
+        assert!(heightfield.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_heightfield_from_image() {
+        let image = image::GrayImage::from_fn(4, 4, |x, _| image::Luma([(x * 85) as u8]));
+        let heightfield = Object::new_heightfield_from_image(
+            &image,
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(3.0, 3.0, 3.0),
+            material(),
+        );
+        let bounds = heightfield.bounding_box(0.0, 0.0).unwrap();
+        vec_approx_equal(bounds.min, Vector::new(0.0, 0.0, 0.0));
+        vec_approx_equal(bounds.max, Vector::new(3.0, 3.0, 3.0));
+
+        let r = Ray::new(Vector::new(2.0, 5.0, 1.5), Vector::new(0.0, -1.0, 0.0), 0.0);
+        let hit = heightfield.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 3.0);
+    }
+
+    #[test]
+    fn object_mesh_hit() {
+        // Unit square in the xy plane, with normals tilted outwards along x
+        let positions = vec![
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(1.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ];
+        let mut mesh = Mesh::new(positions, vec![[0, 1, 2], [0, 2, 3]]);
+        let flat = Object::new_mesh(mesh.clone(), material());
+
+        let left = Vector::new(-1.0, 0.0, 1.0).unit();
+        let right = Vector::new(1.0, 0.0, 1.0).unit();
+        mesh.normals = Some(vec![left, right, right, left]);
+        mesh.uvs = Some(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        mesh.generate_tangents();
+        let smooth = Object::new_mesh(mesh, material());
+
+        let r = Ray::new(
+            Vector::new(0.25, 0.5, 2.0),
+            Vector::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let hit = flat.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 2.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+
+        // Every face borrows the same material
+        let other = Ray::new(
+            Vector::new(0.75, 0.25, 2.0),
+            Vector::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let other_hit = flat.hit(&other, 0.0001, f64::MAX).unwrap();
+        assert!(std::ptr::eq(hit.material, other_hit.material));
+
+        let hit = smooth.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 2.0);
+        assert_approx_eq!(hit.u, 0.25);
+        assert_approx_eq!(hit.v, 0.5);
+        vec_approx_equal(hit.normal, (0.75 * left + 0.25 * right).unit());
+        assert_approx_eq!(Vector::dot(&hit.tangent, &hit.normal), 0.0);
+        assert!(hit.tangent.x > 0.0);
+
+        let r = Ray::new(Vector::new(2.0, 0.5, 2.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(smooth.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_mesh_instance_tangent() {
+        let mut mesh = Mesh::new(
+            vec![
+                Vector::new(0.0, 0.0, 0.0),
+                Vector::new(1.0, 0.0, 0.0),
+                Vector::new(0.0, 1.0, 0.0),
+            ],
+            vec![[0, 1, 2]],
+        );
+        mesh.uvs = Some(vec![(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)]);
+        mesh.generate_tangents();
+        let transform = Matrix::rotation(Vector::new(0.0, 0.0, 1.0), 90.0);
+        let instance =
+            Object::new_instance(Arc::new(Object::new_mesh(mesh, material())), transform);
+
+        let r = Ray::new(
+            Vector::new(-0.25, 0.25, 1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let hit = instance.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+        vec_approx_equal(hit.tangent, Vector::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn object_curve_hit() {
+        // Straight fibre along x
+        let control_points = [
+            Vector::new(-1.0, 0.0, -5.0),
+            Vector::new(-1.0 / 3.0, 0.0, -5.0),
+            Vector::new(1.0 / 3.0, 0.0, -5.0),
+            Vector::new(1.0, 0.0, -5.0),
+        ];
+        let curve = Object::new_curve(control_points, 0.2, 0.2, CurveShape::Cylinder, material());
+
+        let r = Ray::new(Vector::new(0.5, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        let hit = curve.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 5.0);
+        assert_approx_eq!(hit.u, 0.75);
+        assert_approx_eq!(hit.v, 0.5);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, 1.0));
+        vec_approx_equal(hit.tangent, Vector::new(1.0, 0.0, 0.0));
+
+        // Halfway to the edge, the normal is tilted as on a cylinder
+        let r = Ray::new(
+            Vector::new(0.5, 0.05, 0.0),
+            Vector::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let hit = curve.hit(&r, 0.0001, f64::MAX).unwrap();
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.5, 0.75_f64.sqrt()));
+        assert!((hit.v - 0.5).abs() > 0.2);
+
+        let r = Ray::new(Vector::new(0.5, 0.2, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(curve.hit(&r, 0.0001, f64::MAX).is_none());
+        let r = Ray::new(Vector::new(1.5, 0.0, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(curve.hit(&r, 0.0001, f64::MAX).is_none());
+
+        // Arch whose peak is at three quarters of its control height
+        let control_points = [
+            Vector::new(-1.0, 0.0, -5.0),
+            Vector::new(-1.0 / 3.0, 1.0, -5.0),
+            Vector::new(1.0 / 3.0, 1.0, -5.0),
+            Vector::new(1.0, 0.0, -5.0),
+        ];
+        let curve = Object::new_curve(control_points, 0.1, 0.1, CurveShape::Flat, material());
+        let r = Ray::new(
+            Vector::new(0.0, 0.75, 0.0),
+            Vector::new(0.0, 0.0, -1.0),
+            0.0,
+        );
+        let hit = curve.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 5.0);
+        assert_approx_eq!(hit.u, 0.5, 1e-2);
+        let r = Ray::new(Vector::new(0.0, 0.5, 0.0), Vector::new(0.0, 0.0, -1.0), 0.0);
+        assert!(curve.hit(&r, 0.0001, f64::MAX).is_none());
+    }
+
+    #[test]
+    fn object_curve_ribbon() {
+        // Blade standing upright, facing along z
+        let control_points = [
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 2.0, 0.0),
+            Vector::new(0.0, 3.0, 0.0),
+        ];
+        let normal = Vector::new(0.0, 0.0, 1.0);
+        let shape = CurveShape::Ribbon {
+            start_normal: normal,
+            end_normal: normal,
+        };
+        let blade = Object::new_curve(control_points, 0.2, 0.0, shape, material());
+
+        // Seen face on from behind, the normal faces the ray
+        let r = Ray::new(
+            Vector::new(0.05, 0.5, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+            0.0,
+        );
+        let hit = blade.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.t, 5.0);
+        vec_approx_equal(hit.normal, Vector::new(0.0, 0.0, -1.0));
+
+        // Tapered towards the tip
+        let r = Ray::new(
+            Vector::new(0.05, 2.5, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+            0.0,
+        );
+        assert!(blade.hit(&r, 0.0001, f64::MAX).is_none());
+
+        // Edge on, the blade has no width
+        let r = Ray::new(Vector::new(-5.0, 0.5, 0.0), Vector::new(1.0, 0.0, 0.0), 0.0);
+        assert!(blade.hit(&r, 0.0001, f64::MAX).is_none());
+
+        // Turned half over along its length, the blade still has a normal
+        let shape = CurveShape::Ribbon {
+            start_normal: normal,
+            end_normal: -normal,
+        };
+        let blade = Object::new_curve(control_points, 0.2, 0.0, shape, material());
+        let r = Ray::new(
+            Vector::new(0.02, 0.1, -5.0),
+            Vector::new(0.0, 0.0, 1.0),
+            0.0,
+        );
+        let hit = blade.hit(&r, 0.0001, f64::MAX).unwrap();
+        assert_approx_eq!(hit.normal.length(), 1.0);
+        assert!(hit.normal.z < 0.0);
+    }
+
+    #[test]
+    fn object_particles_match_spheres() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(40);
+        let mut positions = vec![];
+        let mut radii = vec![];
+        let mut colours = vec![];
+        for _ in 0..200 {
+            positions.push(Vector::new(
+                rng.gen_range(-10.0, 10.0),
+                rng.gen_range(-10.0, 10.0),
+                rng.gen_range(-10.0, 10.0),
+            ));
+            radii.push(rng.gen_range(0.1, 1.0));
+            colours.push(Vector::new(
+                rng.gen::<f64>(),
+                rng.gen::<f64>(),
+                rng.gen::<f64>(),
+            ));
+        }
+        let list = Object::Multiple(
+            positions
+                .iter()
+                .zip(radii.iter())
+                .map(|(&center, &radius)| Object::new_sphere(center, radius, material()))
+                .collect(),
+        );
+        let particles = Object::new_particles(
+            positions.clone(),
+            radii.clone(),
+            colours.clone(),
+            material(),
+        );
+
+        for _ in 0..200 {
+            let r = Ray::new(
+                Vector::new(0.0, 0.0, 20.0),
+                Vector::new(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), -1.0),
+                0.0,
+            );
+            match (
+                list.hit(&r, 0.0001, f64::MAX),
+                particles.hit(&r, 0.0001, f64::MAX),
+            ) {
+                (None, None) => {}
+                (Some(expected), Some(given)) => {
+                    assert_approx_eq!(given.t, expected.t);
+
+                    // The particle's colour tints the material
+                    let i = (0..positions.len())
+                        .find(|&i| ((positions[i] - given.p).length() - radii[i]).abs() < 1e-6)
+                        .unwrap();
+                    match *given.material {
+                        Material::Lambertian { albedo } => {
+                            vec_approx_equal(albedo, 0.5 * colours[i])
+                        }
+                        _ => panic!("Particle material changed kind!"),
+                    }
+                }
+                _ => panic!("Particles and spheres disagree!"),
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn object_particles_dielectric() {
+        // Glass has no albedo for the colour to tint
+        let _ = Object::new_particles(
+            vec![Vector::new(0.0, 0.0, 0.0)],
+            vec![1.0],
+            vec![Vector::new(1.0, 0.0, 0.0)],
+            Material::new_dielectric(1.5),
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use crate::aabb::Aabb;
+use crate::objects::{HitRecord, Object};
+use crate::ray::Ray;
+
+// Largest number of objects stored in a single leaf
+const MAX_LEAF_SIZE: usize = 2;
+
+// Deepest tree that can be traversed, far beyond what a balanced split of
+// any realistic scene produces
+const MAX_DEPTH: usize = 64;
+
+enum Node {
+    Leaf {
+        bounds: Aabb,
+        start: usize,
+        count: usize,
+    },
+    // The left child immediately follows its parent
+    Interior {
+        bounds: Aabb,
+        right: usize,
+    },
+}
+
+/// Hierarchy of bounding boxes over items stored elsewhere, in an order
+/// chosen by the tree so that each leaf covers a contiguous range of them.
+pub struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    /// Build a tree over items of the given `bounds`, with at most
+    /// `max_leaf_size` items per leaf. Also returns the indices of the items
+    /// in the order they must be stored in.
+    pub fn new(bounds: &[Aabb], max_leaf_size: usize) -> (Tree, Vec<usize>) {
+        let mut order: Vec<usize> = (0..bounds.len()).collect();
+        let mut nodes = vec![];
+        if !order.is_empty() {
+            build(bounds, max_leaf_size, &mut order, 0, &mut nodes);
+        }
+        (Tree { nodes }, order)
+    }
+
+    pub fn bounding_box(&self) -> Option<Aabb> {
+        self.nodes.first().map(|node| match node {
+            Node::Leaf { bounds, .. } | Node::Interior { bounds, .. } => *bounds,
+        })
+    }
+
+    /// Nearest hit of the ray strictly between `t_min` and `t_max`, where
+    /// `hit` tests the stored item at an index over a range of the ray and
+    /// returns any hit along with its parameter.
+    pub fn hit<H>(
+        &self,
+        r: &Ray,
+        t_min: f64,
+        mut t_max: f64,
+        mut hit: impl FnMut(usize, f64, f64) -> Option<(f64, H)>,
+    ) -> Option<H> {
+        let mut closest_hit = None;
+        if self.nodes.is_empty() {
+            return closest_hit;
+        }
+
+        let mut stack = [0; MAX_DEPTH];
+        let mut len = 1;
+        while len > 0 {
+            len -= 1;
+            let index = stack[len];
+            match self.nodes[index] {
+                Node::Leaf {
+                    bounds,
+                    start,
+                    count,
+                } => {
+                    if !bounds.hit(r, t_min, t_max) {
+                        continue;
+                    }
+                    for i in start..start + count {
+                        if let Some((t, item_hit)) = hit(i, t_min, t_max) {
+                            t_max = t;
+                            closest_hit = Some(item_hit);
+                        }
+                    }
+                }
+                Node::Interior { bounds, right } => {
+                    if bounds.hit(r, t_min, t_max) {
+                        stack[len] = right;
+                        stack[len + 1] = index + 1;
+                        len += 2;
+                    }
+                }
+            }
+        }
+
+        closest_hit
+    }
+}
+
+// Recursively split the items at the median centroid along the longest axis,
+// returning the index of the created node.
+fn build(
+    bounds: &[Aabb],
+    max_leaf_size: usize,
+    order: &mut [usize],
+    start: usize,
+    nodes: &mut Vec<Node>,
+) -> usize {
+    let node_bounds = order
+        .iter()
+        .map(|&i| bounds[i])
+        .reduce(|a, b| Aabb::surrounding(&a, &b))
+        .unwrap();
+
+    let index = nodes.len();
+    if order.len() <= max_leaf_size {
+        nodes.push(Node::Leaf {
+            bounds: node_bounds,
+            start,
+            count: order.len(),
+        });
+        return index;
+    }
+
+    let centroids = order
+        .iter()
+        .map(|&i| {
+            let c = bounds[i].centroid();
+            Aabb::new(c, c)
+        })
+        .reduce(|a, b| Aabb::surrounding(&a, &b))
+        .unwrap();
+    let axis = centroids.longest_axis();
+    let mid = order.len() / 2;
+    order.select_nth_unstable_by(mid, |&a, &b| {
+        bounds[a].centroid()[axis].total_cmp(&bounds[b].centroid()[axis])
+    });
+
+    // Reserve the parent so that the left child follows it
+    nodes.push(Node::Leaf {
+        bounds: node_bounds,
+        start,
+        count: 0,
+    });
+    let (left, right) = order.split_at_mut(mid);
+    build(bounds, max_leaf_size, left, start, nodes);
+    let right = build(bounds, max_leaf_size, right, start + mid, nodes);
+    nodes[index] = Node::Interior {
+        bounds: node_bounds,
+        right,
+    };
+
+    index
+}
+
+/// Bounding volume hierarchy over a list of objects.
+///
+/// Objects without a bounding box cannot be placed in the tree and are
+/// instead tested against every ray.
+pub struct Bvh {
+    tree: Tree,
+    objects: Vec<Object>,
+    bounds: Vec<Aabb>,
+    unbounded: Vec<Object>,
+}
+
+impl Bvh {
+    /// Build a hierarchy over `objects`, bounding any motion between the
+    /// times `time0` and `time1`.
+    pub fn new(objects: Vec<Object>, time0: f64, time1: f64) -> Bvh {
+        let mut bounded = vec![];
+        let mut bounds = vec![];
+        let mut unbounded = vec![];
+        for object in objects {
+            match object.bounding_box(time0, time1) {
+                Some(object_bounds) => {
+                    bounds.push(object_bounds);
+                    bounded.push(Some(object));
+                }
+                None => unbounded.push(object),
+            }
+        }
+
+        let (tree, order) = Tree::new(&bounds, MAX_LEAF_SIZE);
+        Bvh {
+            tree,
+            objects: order.iter().map(|&i| bounded[i].take().unwrap()).collect(),
+            bounds: order.iter().map(|&i| bounds[i]).collect(),
+            unbounded,
+        }
+    }
+
+    pub fn bounding_box(&self) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.tree.bounding_box()
+    }
+}
+
+pub fn hit<'a>(bvh: &'a Bvh, r: &Ray, t_min: f64, mut t_max: f64) -> Option<HitRecord<'a>> {
+    let mut closest_hit: Option<HitRecord> = None;
+
+    for object in bvh.unbounded.iter() {
+        if let Some(hit) = object.hit(r, t_min, t_max) {
+            t_max = hit.t;
+            closest_hit = Some(hit);
+        }
+    }
+
+    // Check each object's own bounds first, so that instances only
+    // transform rays that may actually hit them
+    let tree_hit = bvh.tree.hit(r, t_min, t_max, |i, t_min, t_max| {
+        if !bvh.bounds[i].hit(r, t_min, t_max) {
+            return None;
+        }
+        bvh.objects[i].hit(r, t_min, t_max).map(|hit| (hit.t, hit))
+    });
+    tree_hit.or(closest_hit)
+}
@@ -0,0 +1,114 @@
+use std::f64::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::objects::{nearest_in_range, HitRecord, Material};
+use crate::polynomial;
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// Part of the surface a ray crosses.
+#[derive(Copy, Clone)]
+enum Part {
+    Side,
+    Base,
+}
+
+// Every crossing of the ray with the surface, in no particular order.
+fn candidates(center: Vector, radius: f64, height: f64, r: &Ray) -> [Option<(f64, Part)>; 3] {
+    let o = r.origin - center;
+    let d = r.direction;
+    let mut candidates = [None; 3];
+
+    // Curved side, x^2 + z^2 = k (h - y)^2, keeping only the lower nappe
+    let k = (radius / height) * (radius / height);
+    let h = height - o.y;
+    let a = d.x * d.x + d.z * d.z - k * d.y * d.y;
+    let b = 2.0 * (o.x * d.x + o.z * d.z + k * h * d.y);
+    let c = o.x * o.x + o.z * o.z - k * h * h;
+    for (i, &t) in polynomial::solve_quadratic(a, b, c).iter().enumerate() {
+        let y = o.y + t * d.y;
+        if y >= 0.0 && y <= height {
+            candidates[i] = Some((t, Part::Side));
+        }
+    }
+
+    // Base
+    if d.y != 0.0 {
+        let t = -o.y / d.y;
+        let local = o + t * d;
+        if local.x * local.x + local.z * local.z <= radius * radius {
+            candidates[2] = Some((t, Part::Base));
+        }
+    }
+
+    candidates
+}
+
+fn record<'a>(
+    center: Vector,
+    radius: f64,
+    height: f64,
+    material: &'a Material,
+    r: &Ray,
+    (t, part): (f64, Part),
+) -> HitRecord<'a> {
+    let p = r.point_at_parameter(t);
+    let local = p - center;
+    let u = (local.z.atan2(local.x) + PI) / (2.0 * PI);
+    let (normal, v) = match part {
+        Part::Side => {
+            let k = (radius / height) * (radius / height);
+            let gradient = Vector::new(local.x, k * (height - local.y), local.z);
+            let normal = if gradient.squared_length() > 0.0 {
+                gradient.unit()
+            } else {
+                // The apex has no well-defined normal
+                Vector::new(0.0, 1.0, 0.0)
+            };
+            (normal, local.y / height)
+        }
+        Part::Base => {
+            let dist = (local.x * local.x + local.z * local.z).sqrt();
+            (Vector::new(0.0, -1.0, 0.0), dist / radius)
+        }
+    };
+    HitRecord::new(t, p, normal, u, v, material)
+}
+
+/// Every crossing of the ray with the surface, in order along the ray.
+pub fn crossings<'a>(
+    center: Vector,
+    radius: f64,
+    height: f64,
+    material: &'a Material,
+    r: &Ray,
+) -> Vec<HitRecord<'a>> {
+    let mut hits: Vec<HitRecord> = candidates(center, radius, height, r)
+        .iter()
+        .flatten()
+        .map(|&candidate| record(center, radius, height, material, r, candidate))
+        .collect();
+    hits.sort_by(|a, b| a.t.total_cmp(&b.t));
+    hits
+}
+
+pub fn hit<'a>(
+    center: Vector,
+    radius: f64,
+    height: f64,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let candidates = candidates(center, radius, height, r);
+    let nearest = nearest_in_range(candidates.iter().flatten().copied(), t_min, t_max)?;
+    Some(record(center, radius, height, material, r, nearest))
+}
+
+pub fn bounding_box(center: Vector, radius: f64, height: f64) -> Aabb {
+    Aabb::new(
+        center - Vector::new(radius, 0.0, radius),
+        center + Vector::new(radius, height, radius),
+    )
+}
@@ -0,0 +1,147 @@
+use std::iter::Peekable;
+
+use crate::aabb::Aabb;
+use crate::objects::{HitRecord, Interval, Object, Operation};
+use crate::ray::Ray;
+
+// Boundaries of the intervals of one operand, in order along the ray, each
+// tagged with whether it enters the operand.
+struct Boundaries<'a> {
+    intervals: std::vec::IntoIter<Interval<'a>>,
+    exit: Option<HitRecord<'a>>,
+}
+
+impl<'a> Boundaries<'a> {
+    fn new(object: &'a Object, r: &Ray) -> Peekable<Boundaries<'a>> {
+        Boundaries {
+            intervals: object.intervals(r).into_iter(),
+            exit: None,
+        }
+        .peekable()
+    }
+}
+
+impl<'a> Iterator for Boundaries<'a> {
+    type Item = (bool, HitRecord<'a>);
+
+    fn next(&mut self) -> Option<(bool, HitRecord<'a>)> {
+        if let Some(exit) = self.exit.take() {
+            return Some((false, exit));
+        }
+        let interval = self.intervals.next()?;
+        self.exit = Some(interval.exit);
+        Some((true, interval.enter))
+    }
+}
+
+// Sweep along the ray, merging the boundaries of both operands and yielding
+// an interval wherever the combined solid is entered and then left.
+struct Sweep<'a> {
+    operation: Operation,
+    left: Peekable<Boundaries<'a>>,
+    right: Peekable<Boundaries<'a>>,
+    in_left: bool,
+    in_right: bool,
+    enter: Option<HitRecord<'a>>,
+}
+
+impl<'a> Sweep<'a> {
+    fn new(operation: Operation, left: &'a Object, right: &'a Object, r: &Ray) -> Sweep<'a> {
+        Sweep {
+            operation,
+            left: Boundaries::new(left, r),
+            right: Boundaries::new(right, r),
+            in_left: false,
+            in_right: false,
+            enter: None,
+        }
+    }
+}
+
+impl<'a> Iterator for Sweep<'a> {
+    type Item = Interval<'a>;
+
+    fn next(&mut self) -> Option<Interval<'a>> {
+        loop {
+            // Take the nearer boundary, preferring the left operand on ties
+            let is_right = match (self.left.peek(), self.right.peek()) {
+                (Some((_, left)), Some((_, right))) => right.t.total_cmp(&left.t).is_lt(),
+                (Some(_), None) => false,
+                (None, Some(_)) => true,
+                (None, None) => return None,
+            };
+            let (entering, mut hit) = if is_right {
+                self.right.next()?
+            } else {
+                self.left.next()?
+            };
+
+            if is_right {
+                self.in_right = entering;
+            } else {
+                self.in_left = entering;
+            }
+
+            let inside = match self.operation {
+                Operation::Union => self.in_left || self.in_right,
+                Operation::Intersection => self.in_left && self.in_right,
+                Operation::Difference => self.in_left && !self.in_right,
+            };
+
+            // The surface of a subtracted solid faces the other way
+            if let Operation::Difference = self.operation {
+                if is_right {
+                    hit.normal = -hit.normal;
+                }
+            }
+
+            match self.enter.take() {
+                None if inside => self.enter = Some(hit),
+                Some(enter) if !inside => return Some(Interval::new(enter, hit)),
+                enter => self.enter = enter,
+            }
+        }
+    }
+}
+
+pub fn intervals<'a>(
+    operation: Operation,
+    left: &'a Object,
+    right: &'a Object,
+    r: &Ray,
+) -> Vec<Interval<'a>> {
+    Sweep::new(operation, left, right, r).collect()
+}
+
+pub fn hit<'a>(
+    operation: Operation,
+    left: &'a Object,
+    right: &'a Object,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    // Stop sweeping at the first boundary in range
+    Sweep::new(operation, left, right, r)
+        .flat_map(|interval| [interval.enter, interval.exit])
+        .find(|hit| hit.t > t_min && hit.t < t_max)
+}
+
+pub fn bounding_box(
+    operation: Operation,
+    left: &Object,
+    right: &Object,
+    time0: f64,
+    time1: f64,
+) -> Option<Aabb> {
+    let left = left.bounding_box(time0, time1);
+    let right = right.bounding_box(time0, time1);
+    match operation {
+        Operation::Union => Some(Aabb::surrounding(&left?, &right?)),
+        Operation::Intersection => match (left, right) {
+            (Some(left), Some(right)) => Some(Aabb::overlapping(&left, &right)),
+            (bounds, None) | (None, bounds) => bounds,
+        },
+        Operation::Difference => left,
+    }
+}
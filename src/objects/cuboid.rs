@@ -0,0 +1,93 @@
+use crate::aabb::Aabb;
+use crate::objects::{nearest_in_range, HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// Where the ray enters and leaves the box, each with the axis of the face
+// crossed there.
+fn candidates(min: Vector, max: Vector, r: &Ray) -> Option<[(f64, usize); 2]> {
+    let mut t_near = f64::NEG_INFINITY;
+    let mut t_far = f64::INFINITY;
+    let mut near_axis = 0;
+    let mut far_axis = 0;
+
+    // Slab test, remembering which slab bounds the interval at each end
+    for a in 0..3 {
+        let inv_d = 1.0 / r.direction[a];
+        let mut t0 = (min[a] - r.origin[a]) * inv_d;
+        let mut t1 = (max[a] - r.origin[a]) * inv_d;
+        if t0 > t1 {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        if t0 > t_near {
+            t_near = t0;
+            near_axis = a;
+        }
+        if t1 < t_far {
+            t_far = t1;
+            far_axis = a;
+        }
+    }
+
+    if t_near > t_far {
+        return None;
+    }
+    Some([(t_near, near_axis), (t_far, far_axis)])
+}
+
+/// Every crossing of the ray with the surface, in order along the ray.
+pub fn crossings<'a>(
+    min: Vector,
+    max: Vector,
+    material: &'a Material,
+    r: &Ray,
+) -> Vec<HitRecord<'a>> {
+    candidates(min, max, r)
+        .iter()
+        .flatten()
+        .map(|&(t, axis)| face_hit(min, max, material, r, t, axis))
+        .collect()
+}
+
+fn face_hit<'a>(
+    min: Vector,
+    max: Vector,
+    material: &'a Material,
+    r: &Ray,
+    t: f64,
+    axis: usize,
+) -> HitRecord<'a> {
+    let p = r.point_at_parameter(t);
+
+    let mut normal = Vector::new(0.0, 0.0, 0.0);
+    normal[axis] = if (p[axis] - max[axis]).abs() < (p[axis] - min[axis]).abs() {
+        1.0
+    } else {
+        -1.0
+    };
+
+    // Position across the face, taking the other axes in cyclic order
+    let a = (axis + 1) % 3;
+    let b = (axis + 2) % 3;
+    let u = (p[a] - min[a]) / (max[a] - min[a]);
+    let v = (p[b] - min[b]) / (max[b] - min[b]);
+
+    HitRecord::new(t, p, normal, u, v, material)
+}
+
+pub fn hit<'a>(
+    min: Vector,
+    max: Vector,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let candidates = candidates(min, max, r)?;
+    let (t, axis) = nearest_in_range(candidates.iter().copied(), t_min, t_max)?;
+    Some(face_hit(min, max, material, r, t, axis))
+}
+
+pub fn bounding_box(min: Vector, max: Vector) -> Aabb {
+    Aabb::new(min, max)
+}
@@ -0,0 +1,235 @@
+use std::f64::consts::{PI, SQRT_2};
+
+use crate::aabb::Aabb;
+use crate::objects::{CurveShape, HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// Deepest subdivision of the curve before testing a segment
+const MAX_DEPTH: u32 = 10;
+
+// Closest crossing of the ray with a piece of the curve, in a space where the
+// ray starts at the origin and runs along the z axis.
+struct Crossing {
+    z: f64,
+    u: f64,
+    offset: Vector,
+    width: f64,
+}
+
+// Properties of the curve and ray shared by every piece of the search.
+struct Query {
+    widths: (f64, f64),
+    shape: CurveShape,
+    direction: Vector,
+    z_min: f64,
+}
+
+/// Intersect the curve by recursively splitting it until each piece is
+/// close to straight, after Nakamaru and Ohno's method as used in pbrt.
+#[allow(clippy::too_many_arguments)]
+pub fn hit<'a>(
+    control_points: &[Vector; 4],
+    widths: (f64, f64),
+    shape: CurveShape,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let length = r.direction.length();
+    let w = r.direction / length;
+    let (ax, ay) = w.tangents();
+    let cp = control_points.map(|p| {
+        let d = p - r.origin;
+        Vector::new(
+            Vector::dot(&d, &ax),
+            Vector::dot(&d, &ay),
+            Vector::dot(&d, &w),
+        )
+    });
+
+    // Split until the pieces deviate from straight lines by a small fraction
+    // of the width
+    let l0 = (0..2)
+        .map(|i| {
+            let d = cp[i] - 2.0 * cp[i + 1] + cp[i + 2];
+            d.x.abs().max(d.y.abs()).max(d.z.abs())
+        })
+        .fold(0.0, f64::max);
+    let epsilon = 0.05 * widths.0.max(widths.1);
+    let depth = ((SQRT_2 * 6.0 * l0 / (8.0 * epsilon)).log2() / 2.0).round();
+    let depth = if depth > 0.0 {
+        (depth as u32).min(MAX_DEPTH)
+    } else {
+        0
+    };
+
+    let query = Query {
+        widths,
+        shape,
+        direction: w,
+        z_min: t_min * length,
+    };
+    let crossing = intersect(&query, &cp, (0.0, 1.0), depth, t_max * length)?;
+
+    let t = crossing.z / length;
+    let p = r.point_at_parameter(t);
+    let tangent = evaluate(control_points, crossing.u).1;
+
+    // Distance across the curve, from one edge to the other as seen along
+    // the ray
+    let offset = crossing.offset.x * ax + crossing.offset.y * ay;
+    let across = (2.0 * offset.length() / crossing.width).min(1.0);
+    let side = Vector::dot(&Vector::cross(&tangent, &offset), &w).signum();
+    let v = 0.5 + 0.5 * side * across;
+
+    let normal = match shape {
+        CurveShape::Flat => -w,
+        CurveShape::Cylinder => {
+            if across > 0.0 {
+                (1.0 - across * across).sqrt() * -w + across * offset.unit()
+            } else {
+                -w
+            }
+        }
+        CurveShape::Ribbon {
+            start_normal,
+            end_normal,
+        } => {
+            let n = slerp(start_normal, end_normal, crossing.u);
+            if Vector::dot(&n, &w) > 0.0 {
+                -n
+            } else {
+                n
+            }
+        }
+    };
+
+    Some(HitRecord::new(t, p, normal, crossing.u, v, material).with_tangent(tangent))
+}
+
+// Nearest crossing with the piece of the curve spanning `u` no further than
+// `z_max`, splitting it `depth` more times.
+fn intersect(
+    query: &Query,
+    cp: &[Vector; 4],
+    u: (f64, f64),
+    depth: u32,
+    z_max: f64,
+) -> Option<Crossing> {
+    // Skip pieces whose bounds the ray misses
+    let half_width = 0.5 * width(query, u.0).max(width(query, u.1));
+    let (min, max) = cp.iter().skip(1).fold((cp[0], cp[0]), |(min, max), p| {
+        (
+            Vector::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+            Vector::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+        )
+    });
+    if max.x + half_width < 0.0
+        || min.x - half_width > 0.0
+        || max.y + half_width < 0.0
+        || min.y - half_width > 0.0
+        || max.z + half_width < query.z_min
+        || min.z - half_width > z_max
+    {
+        return None;
+    }
+
+    if depth > 0 {
+        let (first_half, second_half) = split(cp);
+        let middle = 0.5 * (u.0 + u.1);
+        let first = intersect(query, &first_half, (u.0, middle), depth - 1, z_max);
+        let z_max = first.as_ref().map_or(z_max, |crossing| crossing.z);
+        let second = intersect(query, &second_half, (middle, u.1), depth - 1, z_max);
+        return second.or(first);
+    }
+
+    // The ray must pass between the planes perpendicular to the piece at
+    // each end
+    let start = (cp[1].y - cp[0].y) * -cp[0].y + cp[0].x * (cp[0].x - cp[1].x);
+    let end = (cp[2].y - cp[3].y) * -cp[3].y + cp[3].x * (cp[3].x - cp[2].x);
+    if start < 0.0 || end < 0.0 {
+        return None;
+    }
+
+    // Closest point to the ray on the line through the ends of the piece
+    let (dx, dy) = (cp[3].x - cp[0].x, cp[3].y - cp[0].y);
+    let denom = dx * dx + dy * dy;
+    if denom == 0.0 {
+        return None;
+    }
+    let s = ((-cp[0].x * dx - cp[0].y * dy) / denom).clamp(0.0, 1.0);
+    let u = u.0 + s * (u.1 - u.0);
+
+    let width = width(query, u);
+    let pc = evaluate(cp, s).0;
+    if pc.x * pc.x + pc.y * pc.y > 0.25 * width * width || pc.z < query.z_min || pc.z > z_max {
+        return None;
+    }
+
+    Some(Crossing {
+        z: pc.z,
+        u,
+        offset: Vector::new(-pc.x, -pc.y, 0.0),
+        width,
+    })
+}
+
+// Width of the curve at `u` as seen along the ray, which narrows ribbons
+// turned edge on.
+fn width(query: &Query, u: f64) -> f64 {
+    let width = query.widths.0 + u * (query.widths.1 - query.widths.0);
+    match query.shape {
+        CurveShape::Ribbon {
+            start_normal,
+            end_normal,
+        } => {
+            let n = slerp(start_normal, end_normal, u);
+            width * Vector::dot(&n, &query.direction).abs()
+        }
+        _ => width,
+    }
+}
+
+// Point and derivative of the cubic Bézier curve at `u`.
+fn evaluate(cp: &[Vector; 4], u: f64) -> (Vector, Vector) {
+    let lerp = |a: Vector, b: Vector| a + u * (b - a);
+    let (a, b, c) = (lerp(cp[0], cp[1]), lerp(cp[1], cp[2]), lerp(cp[2], cp[3]));
+    let (d, e) = (lerp(a, b), lerp(b, c));
+    (lerp(d, e), 3.0 * (e - d))
+}
+
+// Split the curve in half by de Casteljau's algorithm.
+fn split(cp: &[Vector; 4]) -> ([Vector; 4], [Vector; 4]) {
+    let mid = |a: Vector, b: Vector| 0.5 * (a + b);
+    let (a, b, c) = (mid(cp[0], cp[1]), mid(cp[1], cp[2]), mid(cp[2], cp[3]));
+    let (d, e) = (mid(a, b), mid(b, c));
+    let f = mid(d, e);
+    ([cp[0], a, d, f], [f, e, c, cp[3]])
+}
+
+// Spherical interpolation between unit vectors. Opposite vectors turn
+// about an arbitrary axis perpendicular to them.
+fn slerp(a: Vector, b: Vector, u: f64) -> Vector {
+    let theta = Vector::dot(&a, &b).clamp(-1.0, 1.0).acos();
+    if theta < 1e-6 {
+        return (a + u * (b - a)).unit();
+    }
+    if PI - theta < 1e-6 {
+        return (u * PI).cos() * a + (u * PI).sin() * a.tangents().0;
+    }
+    (((1.0 - u) * theta).sin() * a + (u * theta).sin() * b) / theta.sin()
+}
+
+/// Bounds of the control points, which contain the curve, padded by half
+/// its width.
+pub fn bounding_box(control_points: &[Vector; 4], widths: (f64, f64)) -> Aabb {
+    let half_width = 0.5 * widths.0.max(widths.1);
+    let pad = Vector::new(half_width, half_width, half_width);
+    control_points
+        .iter()
+        .map(|&p| Aabb::new(p - pad, p + pad))
+        .reduce(|a, b| Aabb::surrounding(&a, &b))
+        .unwrap()
+}
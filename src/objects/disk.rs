@@ -0,0 +1,52 @@
+use std::f64::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::objects::{HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+pub fn hit<'a>(
+    center: Vector,
+    normal: Vector,
+    radius: f64,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let denom = Vector::dot(&r.direction, &normal);
+    if denom == 0.0 {
+        return None;
+    }
+
+    let t = Vector::dot(&(center - r.origin), &normal) / denom;
+    if t >= t_max || t <= t_min {
+        return None;
+    }
+
+    let p = r.point_at_parameter(t);
+    let offset = p - center;
+    let dist = offset.length();
+    if dist > radius {
+        return None;
+    }
+
+    // Polar coordinates around the centre
+    let (tangent, bitangent) = normal.tangents();
+    let phi = Vector::dot(&offset, &bitangent).atan2(Vector::dot(&offset, &tangent));
+    let u = (phi + PI) / (2.0 * PI);
+    let v = dist / radius;
+
+    Some(HitRecord::new(t, p, normal, u, v, material))
+}
+
+pub fn bounding_box(center: Vector, normal: Vector, radius: f64) -> Aabb {
+    // The extent along each axis shrinks as the disk faces along it
+    let extent = Vector::new(
+        radius * (1.0 - normal.x * normal.x).max(0.0).sqrt(),
+        radius * (1.0 - normal.y * normal.y).max(0.0).sqrt(),
+        radius * (1.0 - normal.z * normal.z).max(0.0).sqrt(),
+    );
+    let padding = Vector::new(0.0001, 0.0001, 0.0001);
+    Aabb::new(center - extent - padding, center + extent + padding)
+}
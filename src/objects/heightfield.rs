@@ -0,0 +1,220 @@
+use crate::aabb::Aabb;
+use crate::objects::{triangle, HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+/// Regular grid of height samples over a rectangle in the xz plane. Each
+/// cell between four samples is split into two triangles.
+pub struct Heightfield {
+    heights: Vec<f64>,
+    normals: Vec<Vector>,
+    columns: usize,
+    rows: usize,
+    corner: Vector,
+    size: Vector,
+    bounds: Aabb,
+    material: Material,
+}
+
+impl Heightfield {
+    /// Grid of `heights` in row-major order, with columns running along x
+    /// and rows along z. The grid spans `size.x` by `size.z` from `corner`,
+    /// and each height is scaled by `size.y` and raised by `corner.y`.
+    pub fn new(
+        heights: Vec<f64>,
+        columns: usize,
+        corner: Vector,
+        size: Vector,
+        material: Material,
+    ) -> Heightfield {
+        assert!(
+            columns >= 2 && heights.len() % columns == 0 && heights.len() / columns >= 2,
+            "Heightfield requires a grid of at least 2x2 samples!"
+        );
+        let rows = heights.len() / columns;
+
+        let (low, high) = heights
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &h| {
+                (low.min(h), high.max(h))
+            });
+        let a = corner + Vector::new(0.0, low * size.y, 0.0);
+        let b = corner + Vector::new(size.x, high * size.y, size.z);
+        let bounds = Aabb::new(
+            Vector::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            Vector::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        );
+
+        let mut heightfield = Heightfield {
+            heights,
+            normals: vec![],
+            columns,
+            rows,
+            corner,
+            size,
+            bounds,
+            material,
+        };
+        heightfield.normals = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| heightfield.vertex_normal(i, j))
+            .collect();
+        heightfield
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.bounds
+    }
+
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            self.size.x / (self.columns - 1) as f64,
+            self.size.z / (self.rows - 1) as f64,
+        )
+    }
+
+    fn height(&self, i: usize, j: usize) -> f64 {
+        self.corner.y + self.heights[j * self.columns + i] * self.size.y
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vector {
+        let (dx, dz) = self.cell_size();
+        Vector::new(
+            self.corner.x + i as f64 * dx,
+            self.height(i, j),
+            self.corner.z + j as f64 * dz,
+        )
+    }
+
+    // Normal from the slope by central differences, or one-sided differences
+    // along the edges of the grid.
+    fn vertex_normal(&self, i: usize, j: usize) -> Vector {
+        let (dx, dz) = self.cell_size();
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.columns - 1));
+        let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.rows - 1));
+        let slope_x = (self.height(i1, j) - self.height(i0, j)) / ((i1 - i0) as f64 * dx);
+        let slope_z = (self.height(i, j1) - self.height(i, j0)) / ((j1 - j0) as f64 * dz);
+        Vector::new(-slope_x, 1.0, -slope_z).unit()
+    }
+}
+
+/// Walk the cells under the ray in order with a 2D digital differential
+/// analyser, testing only the triangles of cells the ray passes over.
+pub fn hit<'a>(
+    heightfield: &'a Heightfield,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let (t_start, t_end) = heightfield.bounds.clip(r, t_min, t_max)?;
+    let (dx, dz) = heightfield.cell_size();
+    let (columns, rows) = (heightfield.columns as isize, heightfield.rows as isize);
+
+    // Cell containing the point where the ray enters the grid
+    let p = r.point_at_parameter(t_start);
+    let cell = |x: f64, origin: f64, size: f64, count: isize| {
+        (((x - origin) / size).floor() as isize).clamp(0, count - 2)
+    };
+    let mut i = cell(p.x, heightfield.corner.x, dx, columns);
+    let mut j = cell(p.z, heightfield.corner.z, dz, rows);
+
+    // Distance along the ray to the next cell boundary on each axis, and
+    // between successive boundaries
+    let axis = |cell: isize, origin: f64, size: f64, o: f64, d: f64| {
+        if d == 0.0 {
+            return (0, f64::INFINITY, f64::INFINITY);
+        }
+        let step = if d > 0.0 { 1 } else { -1 };
+        let boundary = origin + (cell + if d > 0.0 { 1 } else { 0 }) as f64 * size;
+        (step, (boundary - o) / d, size / d.abs())
+    };
+    let (step_i, mut next_x, delta_x) =
+        axis(i, heightfield.corner.x, dx, r.origin.x, r.direction.x);
+    let (step_j, mut next_z, delta_z) =
+        axis(j, heightfield.corner.z, dz, r.origin.z, r.direction.z);
+
+    let mut t_enter = t_start;
+    loop {
+        let t_exit = next_x.min(next_z).min(t_end);
+        if let Some(hit) = hit_cell(
+            heightfield,
+            i as usize,
+            j as usize,
+            r,
+            (t_enter, t_exit),
+            t_min,
+            t_max,
+        ) {
+            return Some(hit);
+        }
+        if t_exit >= t_end {
+            return None;
+        }
+
+        if next_x < next_z {
+            i += step_i;
+            t_enter = next_x;
+            next_x += delta_x;
+        } else {
+            j += step_j;
+            t_enter = next_z;
+            next_z += delta_z;
+        }
+        if i < 0 || i > columns - 2 || j < 0 || j > rows - 2 {
+            return None;
+        }
+    }
+}
+
+// Nearest hit on the two triangles of the cell at column `i` and row `j`,
+// which the ray passes over between the distances `span`.
+fn hit_cell<'a>(
+    heightfield: &'a Heightfield,
+    i: usize,
+    j: usize,
+    r: &Ray,
+    span: (f64, f64),
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+
+    // Skip cells the ray passes entirely above or below
+    let (low, high) = corners
+        .iter()
+        .map(|&(i, j)| heightfield.height(i, j))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), h| {
+            (low.min(h), high.max(h))
+        });
+    let y0 = r.origin.y + span.0 * r.direction.y;
+    let y1 = r.origin.y + span.1 * r.direction.y;
+    if y0.min(y1) > high || y0.max(y1) < low {
+        return None;
+    }
+
+    let mut closest: Option<HitRecord> = None;
+    for triangle in [[0, 1, 2], [0, 2, 3]].iter() {
+        let [a, b, c] = triangle.map(|k| corners[k]);
+        let vertices = [
+            heightfield.vertex(a.0, a.1),
+            heightfield.vertex(b.0, b.1),
+            heightfield.vertex(c.0, c.1),
+        ];
+        let t_far = closest.as_ref().map_or(t_max, |hit| hit.t);
+        if let Some((t, b1, b2)) = triangle::intersect(&vertices, r, t_min, t_far) {
+            let b0 = 1.0 - b1 - b2;
+            let normal = (b0 * heightfield.normals[a.1 * heightfield.columns + a.0]
+                + b1 * heightfield.normals[b.1 * heightfield.columns + b.0]
+                + b2 * heightfield.normals[c.1 * heightfield.columns + c.0])
+                .unit();
+
+            // Position across the whole grid
+            let p = r.point_at_parameter(t);
+            let u = (p.x - heightfield.corner.x) / heightfield.size.x;
+            let v = (p.z - heightfield.corner.z) / heightfield.size.z;
+
+            closest = Some(HitRecord::new(t, p, normal, u, v, &heightfield.material));
+        }
+    }
+    closest
+}
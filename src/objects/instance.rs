@@ -0,0 +1,51 @@
+use crate::matrix::Matrix;
+use crate::objects::{HitRecord, Interval, Object};
+use crate::ray::Ray;
+
+// Ray in object space. The direction is not renormalised, so the ray
+// parameter is the same in both spaces.
+fn to_local(inverse: &Matrix, r: &Ray) -> Ray {
+    Ray::new(
+        inverse.transform_point(&r.origin),
+        inverse.transform_vector(&r.direction),
+        r.time,
+    )
+}
+
+fn to_world<'a>(transform: &Matrix, inverse: &Matrix, mut hit: HitRecord<'a>) -> HitRecord<'a> {
+    hit.p = transform.transform_point(&hit.p);
+    hit.normal = inverse.transform_normal(&hit.normal).unit();
+    let tangent = transform.transform_vector(&hit.tangent);
+    hit.with_tangent(tangent)
+}
+
+pub fn hit<'a>(
+    transform: &Matrix,
+    inverse: &Matrix,
+    object: &'a Object,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    object
+        .hit(&to_local(inverse, r), t_min, t_max)
+        .map(|hit| to_world(transform, inverse, hit))
+}
+
+pub fn intervals<'a>(
+    transform: &Matrix,
+    inverse: &Matrix,
+    object: &'a Object,
+    r: &Ray,
+) -> Vec<Interval<'a>> {
+    object
+        .intervals(&to_local(inverse, r))
+        .into_iter()
+        .map(|interval| {
+            Interval::new(
+                to_world(transform, inverse, interval.enter),
+                to_world(transform, inverse, interval.exit),
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,50 @@
+use crate::aabb::Aabb;
+use crate::objects::{HitRecord, Interval, Object};
+use crate::ray::Ray;
+use crate::trajectory::Trajectory;
+
+pub fn hit<'a>(
+    trajectory: &Trajectory,
+    object: &'a Object,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    // Move the ray rather than the object
+    let offset = trajectory.at(r.time);
+    let moved = Ray::new(r.origin - offset, r.direction, r.time);
+
+    object.hit(&moved, t_min, t_max).map(|mut hit| {
+        hit.p += offset;
+        hit
+    })
+}
+
+pub fn intervals<'a>(trajectory: &Trajectory, object: &'a Object, r: &Ray) -> Vec<Interval<'a>> {
+    let offset = trajectory.at(r.time);
+    let moved = Ray::new(r.origin - offset, r.direction, r.time);
+
+    object
+        .intervals(&moved)
+        .into_iter()
+        .map(|mut interval| {
+            interval.enter.p += offset;
+            interval.exit.p += offset;
+            interval
+        })
+        .collect()
+}
+
+pub fn bounding_box(
+    trajectory: &Trajectory,
+    object: &Object,
+    time0: f64,
+    time1: f64,
+) -> Option<Aabb> {
+    let bounds = object.bounding_box(time0, time1)?;
+    trajectory
+        .extremes(time0, time1)
+        .into_iter()
+        .map(|offset| bounds.translate(offset))
+        .reduce(|a, b| Aabb::surrounding(&a, &b))
+}
@@ -0,0 +1,71 @@
+use crate::aabb::Aabb;
+use crate::objects::bvh::Tree;
+use crate::objects::{sphere, HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// Largest number of particles stored in a single leaf
+const MAX_LEAF_SIZE: usize = 4;
+
+/// Spherical particles sharing a material, each tinted by its own colour.
+///
+/// Particles are stored in flat arrays, in the order of the leaves of a
+/// bounding volume hierarchy over them. Each particle keeps its material
+/// tinted once up front, so that hits only borrow it.
+pub struct Particles {
+    positions: Vec<Vector>,
+    radii: Vec<f64>,
+    materials: Vec<Material>,
+    tree: Tree,
+}
+
+impl Particles {
+    pub fn new(
+        positions: Vec<Vector>,
+        radii: Vec<f64>,
+        colours: Vec<Vector>,
+        material: Material,
+    ) -> Particles {
+        assert!(
+            positions.len() == radii.len() && positions.len() == colours.len(),
+            "Particles require a radius and colour for every position!"
+        );
+
+        let bounds: Vec<Aabb> = positions
+            .iter()
+            .zip(radii.iter())
+            .map(|(&position, &radius)| particle_bounds(position, radius))
+            .collect();
+        let (tree, order) = Tree::new(&bounds, MAX_LEAF_SIZE);
+        let materials = order
+            .iter()
+            .map(|&i| material.tint(colours[i]))
+            .collect::<Option<Vec<Material>>>()
+            .expect("Particles require a material which can be tinted!");
+
+        Particles {
+            positions: order.iter().map(|&i| positions[i]).collect(),
+            radii: order.iter().map(|&i| radii[i]).collect(),
+            materials,
+            tree,
+        }
+    }
+
+    pub fn bounding_box(&self) -> Option<Aabb> {
+        self.tree.bounding_box()
+    }
+}
+
+fn particle_bounds(position: Vector, radius: f64) -> Aabb {
+    let extent = Vector::new(radius, radius, radius);
+    Aabb::new(position - extent, position + extent)
+}
+
+pub fn hit<'a>(particles: &'a Particles, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+    particles.tree.hit(r, t_min, t_max, |i, t_min, t_max| {
+        let center = particles.positions[i];
+        let radius = particles.radii[i];
+        sphere::hit(center, radius, &particles.materials[i], r, t_min, t_max)
+            .map(|hit| (hit.t, hit))
+    })
+}
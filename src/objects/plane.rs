@@ -0,0 +1,31 @@
+use crate::objects::{HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+pub fn hit<'a>(
+    point: Vector,
+    normal: Vector,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let denom = Vector::dot(&r.direction, &normal);
+    if denom == 0.0 {
+        return None;
+    }
+
+    let t = Vector::dot(&(point - r.origin), &normal) / denom;
+    if t < t_max && t > t_min {
+        let p = r.point_at_parameter(t);
+
+        // Distances along the plane, so textures tile in world units
+        let (tangent, bitangent) = normal.tangents();
+        let u = Vector::dot(&(p - point), &tangent);
+        let v = Vector::dot(&(p - point), &bitangent);
+
+        Some(HitRecord::new(t, p, normal, u, v, material))
+    } else {
+        None
+    }
+}
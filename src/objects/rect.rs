@@ -0,0 +1,57 @@
+use crate::aabb::Aabb;
+use crate::objects::{Axis, HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// Indices of the normal axis and of the two axes spanning the rectangle.
+fn axes(axis: Axis) -> (usize, usize, usize) {
+    let k = axis.index();
+    (k, (k + 1) % 3, (k + 2) % 3)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn hit<'a>(
+    axis: Axis,
+    (a0, a1): (f64, f64),
+    (b0, b1): (f64, f64),
+    k: f64,
+    flip: bool,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let (n, a, b) = axes(axis);
+
+    let t = (k - r.origin[n]) / r.direction[n];
+    if !(t < t_max && t > t_min) {
+        return None;
+    }
+
+    let p = r.point_at_parameter(t);
+    if p[a] < a0 || p[a] > a1 || p[b] < b0 || p[b] > b1 {
+        return None;
+    }
+
+    let mut normal = Vector::new(0.0, 0.0, 0.0);
+    normal[n] = if flip { -1.0 } else { 1.0 };
+    let u = (p[a] - a0) / (a1 - a0);
+    let v = (p[b] - b0) / (b1 - b0);
+
+    Some(HitRecord::new(t, p, normal, u, v, material))
+}
+
+pub fn bounding_box(axis: Axis, (a0, a1): (f64, f64), (b0, b1): (f64, f64), k: f64) -> Aabb {
+    let (n, a, b) = axes(axis);
+
+    // Pad the thickness so the box never has zero volume
+    let mut min = Vector::new(0.0, 0.0, 0.0);
+    let mut max = Vector::new(0.0, 0.0, 0.0);
+    min[n] = k - 0.0001;
+    max[n] = k + 0.0001;
+    min[a] = a0;
+    max[a] = a1;
+    min[b] = b0;
+    max[b] = b1;
+    Aabb::new(min, max)
+}
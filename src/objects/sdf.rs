@@ -0,0 +1,64 @@
+use crate::aabb::Aabb;
+use crate::objects::{HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+#[allow(clippy::too_many_arguments)]
+pub fn hit<'a>(
+    distance: &dyn Fn(Vector) -> f64,
+    bounds: &Aabb,
+    epsilon: f64,
+    max_steps: u32,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    // Only march through the part of the ray inside the bounds
+    let (mut t, t_end) = bounds.clip(r, t_min, t_max)?;
+    let length = r.direction.length();
+
+    // Rays entering the bounds come from outside the surface. Rays starting
+    // inside may be leaving the surface from within epsilon of it, so step
+    // off the surface before deciding which side of it the ray is on.
+    let mut steps = 0;
+    let side = if t > t_min {
+        1.0
+    } else {
+        let mut d = distance(r.point_at_parameter(t));
+        while d.abs() < epsilon && steps < max_steps {
+            t += epsilon / length;
+            d = distance(r.point_at_parameter(t));
+            steps += 1;
+        }
+        d.signum()
+    };
+
+    // Sphere tracing: the distance to the surface is always safe to step.
+    // Allow for surfaces which touch their bounds.
+    while steps < max_steps && t < t_end + epsilon / length {
+        let d = side * distance(r.point_at_parameter(t));
+        if d < epsilon {
+            let p = r.point_at_parameter(t);
+            let normal = gradient(distance, p, epsilon).unit();
+            return Some(HitRecord::new(t, p, normal, 0.0, 0.0, material));
+        }
+        t += d / length;
+        steps += 1;
+    }
+
+    None
+}
+
+// Gradient of the distance function by central differences at the vertices
+// of a tetrahedron, which needs only four evaluations.
+fn gradient(distance: &dyn Fn(Vector) -> f64, p: Vector, h: f64) -> Vector {
+    let k0 = Vector::new(1.0, -1.0, -1.0);
+    let k1 = Vector::new(-1.0, -1.0, 1.0);
+    let k2 = Vector::new(-1.0, 1.0, -1.0);
+    let k3 = Vector::new(1.0, 1.0, 1.0);
+    k0 * distance(p + h * k0)
+        + k1 * distance(p + h * k1)
+        + k2 * distance(p + h * k2)
+        + k3 * distance(p + h * k3)
+}
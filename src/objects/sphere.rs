@@ -1,17 +1,14 @@
 #![allow(clippy::many_single_char_names)]
 
+use std::f64::consts::PI;
+
+use crate::aabb::Aabb;
 use crate::objects::{HitRecord, Material};
 use crate::ray::Ray;
 use crate::vector::Vector;
 
-pub fn hit(
-    center: Vector,
-    radius: f64,
-    material: Material,
-    r: &Ray,
-    t_min: f64,
-    t_max: f64,
-) -> Option<HitRecord> {
+// Parameters along the ray where it enters and leaves the sphere.
+fn candidates(center: Vector, radius: f64, r: &Ray) -> Option<[f64; 2]> {
     let oc = r.origin - center;
 
     let a = Vector::dot(&r.direction, &r.direction);
@@ -20,27 +17,63 @@ pub fn hit(
 
     let discriminant = b * b - a * c;
     if discriminant > 0.0 {
-        let soln = (-b - (b * b - a * c).sqrt()) / a;
-
-        let t;
-        let p;
-        let normal;
-
-        if (soln < t_max) && (soln > t_min) {
-            t = soln;
-            p = r.point_at_parameter(soln);
-            normal = (p - center) / radius;
-            return Some(HitRecord::new(t, p, normal, material));
-        }
-        let soln = (-b + (b * b - a * c).sqrt()) / a;
-        if (soln < t_max) && (soln > t_min) {
-            t = soln;
-            p = r.point_at_parameter(soln);
-            normal = (p - center) / radius;
-            return Some(HitRecord::new(t, p, normal, material));
-        }
-        None
+        let root = discriminant.sqrt();
+        Some([(-b - root) / a, (-b + root) / a])
     } else {
         None
     }
 }
+
+pub fn hit<'a>(
+    center: Vector,
+    radius: f64,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let t = candidates(center, radius, r)?
+        .iter()
+        .copied()
+        .find(|&t| t > t_min && t < t_max)?;
+    Some(record(center, radius, material, r, t))
+}
+
+/// Every crossing of the ray with the surface, in order along the ray.
+pub fn crossings<'a>(
+    center: Vector,
+    radius: f64,
+    material: &'a Material,
+    r: &Ray,
+) -> Vec<HitRecord<'a>> {
+    candidates(center, radius, r)
+        .iter()
+        .flatten()
+        .map(|&t| record(center, radius, material, r, t))
+        .collect()
+}
+
+fn record<'a>(
+    center: Vector,
+    radius: f64,
+    material: &'a Material,
+    r: &Ray,
+    t: f64,
+) -> HitRecord<'a> {
+    let p = r.point_at_parameter(t);
+    let normal = (p - center) / radius;
+    let (u, v) = uv(&((p - center) / radius.abs()));
+    HitRecord::new(t, p, normal, u, v, material)
+}
+
+// Longitude and latitude of a point on the unit sphere, both scaled to [0, 1].
+fn uv(p: &Vector) -> (f64, f64) {
+    let phi = p.z.atan2(p.x);
+    let theta = p.y.clamp(-1.0, 1.0).asin();
+    (1.0 - (phi + PI) / (2.0 * PI), (theta + PI / 2.0) / PI)
+}
+
+pub fn bounding_box(center: Vector, radius: f64) -> Aabb {
+    let extent = Vector::new(radius.abs(), radius.abs(), radius.abs());
+    Aabb::new(center - extent, center + extent)
+}
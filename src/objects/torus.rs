@@ -0,0 +1,98 @@
+use std::f64::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::objects::{HitRecord, Material};
+use crate::polynomial::{self, Roots};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+// Parameters along the ray of every crossing with the surface, in
+// ascending order.
+fn candidates(center: Vector, major_radius: f64, minor_radius: f64, r: &Ray) -> Roots {
+    let length = r.direction.length();
+    let d = r.direction / length;
+
+    // Start the ray just outside the bounding sphere to keep the quartic
+    // coefficients well conditioned
+    let o = r.origin - center;
+    let shift = (Vector::dot(&-o, &d) - (major_radius + minor_radius)).max(0.0);
+    let o = o + shift * d;
+
+    // (|p|^2 + R^2 - r^2)^2 = 4 R^2 (x^2 + z^2) along the ray
+    let sq_major = major_radius * major_radius;
+    let od = Vector::dot(&o, &d);
+    let k = Vector::dot(&o, &o) + sq_major - minor_radius * minor_radius;
+    let mut roots = polynomial::solve_quartic(
+        1.0,
+        4.0 * od,
+        4.0 * od * od + 2.0 * k - 4.0 * sq_major * (d.x * d.x + d.z * d.z),
+        4.0 * od * k - 8.0 * sq_major * (o.x * d.x + o.z * d.z),
+        k * k - 4.0 * sq_major * (o.x * o.x + o.z * o.z),
+    );
+    for root in roots.iter_mut() {
+        *root = (*root + shift) / length;
+    }
+    roots
+}
+
+fn record<'a>(
+    center: Vector,
+    major_radius: f64,
+    minor_radius: f64,
+    material: &'a Material,
+    r: &Ray,
+    t: f64,
+) -> HitRecord<'a> {
+    let p = r.point_at_parameter(t);
+    let local = p - center;
+
+    let sq_major = major_radius * major_radius;
+    let s = local.squared_length() - sq_major - minor_radius * minor_radius;
+    let normal = Vector::new(local.x * s, local.y * (s + 2.0 * sq_major), local.z * s).unit();
+
+    // Angle around the ring, then around the tube
+    let ring = (local.x * local.x + local.z * local.z).sqrt();
+    let u = (local.z.atan2(local.x) + PI) / (2.0 * PI);
+    let v = (local.y.atan2(ring - major_radius) + PI) / (2.0 * PI);
+
+    HitRecord::new(t, p, normal, u, v, material)
+}
+
+/// Every crossing of the ray with the surface, in order along the ray.
+pub fn crossings<'a>(
+    center: Vector,
+    major_radius: f64,
+    minor_radius: f64,
+    material: &'a Material,
+    r: &Ray,
+) -> Vec<HitRecord<'a>> {
+    candidates(center, major_radius, minor_radius, r)
+        .iter()
+        .map(|&t| record(center, major_radius, minor_radius, material, r, t))
+        .collect()
+}
+
+pub fn hit<'a>(
+    center: Vector,
+    major_radius: f64,
+    minor_radius: f64,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let t = candidates(center, major_radius, minor_radius, r)
+        .iter()
+        .copied()
+        .find(|&t| t > t_min && t < t_max)?;
+    Some(record(center, major_radius, minor_radius, material, r, t))
+}
+
+pub fn bounding_box(center: Vector, major_radius: f64, minor_radius: f64) -> Aabb {
+    let extent = Vector::new(
+        major_radius + minor_radius,
+        minor_radius,
+        major_radius + minor_radius,
+    );
+    Aabb::new(center - extent, center + extent)
+}
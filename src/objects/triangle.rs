@@ -0,0 +1,85 @@
+use crate::aabb::Aabb;
+use crate::mesh::Mesh;
+use crate::objects::{HitRecord, Material};
+use crate::ray::Ray;
+use crate::vector::Vector;
+
+pub fn hit<'a>(
+    mesh: &Mesh,
+    index: usize,
+    material: &'a Material,
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let face = mesh.faces[index];
+    let vertices = face.map(|i| mesh.positions[i]);
+    let (t, b1, b2) = intersect(&vertices, r, t_min, t_max)?;
+    let b0 = 1.0 - b1 - b2;
+    let [a, b, c] = face;
+    let interpolate = |values: &[Vector]| b0 * values[a] + b1 * values[b] + b2 * values[c];
+
+    let normal = match &mesh.normals {
+        Some(normals) => interpolate(normals).unit(),
+        None => Vector::cross(&(vertices[1] - vertices[0]), &(vertices[2] - vertices[0])).unit(),
+    };
+    let (u, v) = match &mesh.uvs {
+        Some(uvs) => (
+            b0 * uvs[a].0 + b1 * uvs[b].0 + b2 * uvs[c].0,
+            b0 * uvs[a].1 + b1 * uvs[b].1 + b2 * uvs[c].1,
+        ),
+        None => (b1, b2),
+    };
+
+    let p = r.point_at_parameter(t);
+    let hit = HitRecord::new(t, p, normal, u, v, material);
+    match &mesh.tangents {
+        Some(tangents) => Some(hit.with_tangent(interpolate(tangents))),
+        None => Some(hit),
+    }
+}
+
+pub fn bounding_box(mesh: &Mesh, index: usize) -> Aabb {
+    let [a, b, c] = mesh.faces[index].map(|i| mesh.positions[i]);
+    Aabb::surrounding(
+        &Aabb::new(a, a),
+        &Aabb::surrounding(&Aabb::new(b, b), &Aabb::new(c, c)),
+    )
+}
+
+/// Möller–Trumbore ray-triangle intersection, giving the distance along the
+/// ray and the barycentric weights of the second and third vertices.
+pub fn intersect(
+    vertices: &[Vector; 3],
+    r: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, f64, f64)> {
+    let edge1 = vertices[1] - vertices[0];
+    let edge2 = vertices[2] - vertices[0];
+    let p = Vector::cross(&r.direction, &edge2);
+    let det = Vector::dot(&edge1, &p);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+
+    let s = r.origin - vertices[0];
+    let b1 = Vector::dot(&s, &p) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+
+    let q = Vector::cross(&s, &edge1);
+    let b2 = Vector::dot(&r.direction, &q) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = Vector::dot(&edge2, &q) * inv_det;
+    if t > t_min && t < t_max {
+        Some((t, b1, b2))
+    } else {
+        None
+    }
+}
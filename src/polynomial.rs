@@ -0,0 +1,246 @@
+use std::f64::consts::PI;
+use std::ops::{Deref, DerefMut};
+
+// Closed-form solvers for the real roots of low-order polynomials, after
+// Schwarze's "Cubic and Quartic Roots" in Graphics Gems (1990).
+//
+// Each solver returns the real roots in ascending order.
+
+// Coefficients smaller than this are treated as zero
+const EPSILON: f64 = 1e-9;
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPSILON
+}
+
+/// Up to four real roots, held without allocating so that solvers can be
+/// called for every ray.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Roots {
+    values: [f64; 4],
+    len: usize,
+}
+
+impl Roots {
+    fn new(roots: &[f64]) -> Roots {
+        let mut result = Roots::default();
+        for &root in roots {
+            result.push(root);
+        }
+        result
+    }
+
+    fn push(&mut self, root: f64) {
+        self.values[self.len] = root;
+        self.len += 1;
+    }
+
+    fn sort(&mut self) {
+        self.sort_unstable_by(|x, y| x.total_cmp(y));
+    }
+}
+
+impl Deref for Roots {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        &self.values[..self.len]
+    }
+}
+
+impl DerefMut for Roots {
+    fn deref_mut(&mut self) -> &mut [f64] {
+        &mut self.values[..self.len]
+    }
+}
+
+/// Solve `a x^2 + b x + c = 0`.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Roots {
+    if a == 0.0 {
+        return if b == 0.0 {
+            Roots::default()
+        } else {
+            Roots::new(&[-c / b])
+        };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return Roots::default();
+    }
+
+    // Avoid cancellation between -b and the square root
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let mut roots = if q == 0.0 {
+        Roots::new(&[0.0, 0.0])
+    } else {
+        Roots::new(&[q / a, c / q])
+    };
+    roots.sort();
+    roots
+}
+
+/// Solve `a x^3 + b x^2 + c x + d = 0`.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Roots {
+    if a == 0.0 {
+        return solve_quadratic(b, c, d);
+    }
+
+    // Normal form x^3 + A x^2 + B x + C = 0
+    let (a, b, c) = (b / a, c / a, d / a);
+
+    // Substitute x = y - A/3 to eliminate the quadric term: y^3 + 3p y + 2q = 0
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+
+    // Cardano's formula
+    let cb_p = p * p * p;
+    let discriminant = q * q + cb_p;
+
+    let mut roots = if is_zero(discriminant) {
+        if is_zero(q) {
+            Roots::new(&[0.0])
+        } else {
+            let u = (-q).cbrt();
+            Roots::new(&[2.0 * u, -u])
+        }
+    } else if discriminant < 0.0 {
+        // Casus irreducibilis: three real roots
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        Roots::new(&[
+            t * phi.cos(),
+            -t * (phi + PI / 3.0).cos(),
+            -t * (phi - PI / 3.0).cos(),
+        ])
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        Roots::new(&[(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt()])
+    };
+
+    let sub = a / 3.0;
+    for root in roots.iter_mut() {
+        *root -= sub;
+    }
+    roots.sort();
+    roots
+}
+
+/// Solve `a x^4 + b x^3 + c x^2 + d x + e = 0`.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Roots {
+    if a == 0.0 {
+        return solve_cubic(b, c, d, e);
+    }
+
+    // Normal form x^4 + A x^3 + B x^2 + C x + D = 0
+    let (a, b, c, d) = (b / a, c / a, d / a, e / a);
+
+    // Substitute x = y - A/4 to eliminate the cubic term:
+    // y^4 + p y^2 + q y + r = 0
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + c;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * c / 4.0 + d;
+
+    let mut roots = if is_zero(r) {
+        // No absolute term: y (y^3 + p y + q) = 0
+        let mut roots = solve_cubic(1.0, 0.0, p, q);
+        roots.push(0.0);
+        roots
+    } else {
+        // Take one root of the resolvent cubic...
+        let z = solve_cubic(1.0, -p / 2.0, -r, r * p / 2.0 - q * q / 8.0)[0];
+
+        // ...to build two quadratic equations
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if is_zero(u) {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return Roots::default();
+        };
+        let v = if is_zero(v) {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return Roots::default();
+        };
+        let v = if q < 0.0 { -v } else { v };
+
+        let mut roots = solve_quadratic(1.0, v, z - u);
+        for &root in solve_quadratic(1.0, -v, z + u).iter() {
+            roots.push(root);
+        }
+        roots
+    };
+
+    // Resubstitute and polish with Newton's method, since the closed form
+    // loses precision for the widely spread coefficients of ray tests
+    let sub = a / 4.0;
+    for root in roots.iter_mut() {
+        let mut x = *root - sub;
+        for _ in 0..2 {
+            let f = (((x + a) * x + b) * x + c) * x + d;
+            let df = ((4.0 * x + 3.0 * a) * x + 2.0 * b) * x + c;
+            if df != 0.0 {
+                x -= f / df;
+            }
+        }
+        *root = x;
+    }
+    roots.sort();
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn roots_approx_equal(given: Roots, expected: Vec<f64>) {
+        assert_eq!(given.len(), expected.len(), "{:?}", given);
+        for (g, e) in given.iter().zip(expected.iter()) {
+            assert_approx_eq!(g, e);
+        }
+    }
+
+    #[test]
+    fn polynomial_solve_quadratic() {
+        roots_approx_equal(solve_quadratic(1.0, -3.0, 2.0), vec![1.0, 2.0]);
+        roots_approx_equal(solve_quadratic(2.0, 0.0, -8.0), vec![-2.0, 2.0]);
+        roots_approx_equal(solve_quadratic(1.0, 0.0, 1.0), vec![]);
+        roots_approx_equal(solve_quadratic(0.0, 2.0, -1.0), vec![0.5]);
+    }
+
+    #[test]
+    fn polynomial_solve_cubic() {
+        // (x - 1)(x - 2)(x - 3)
+        roots_approx_equal(solve_cubic(1.0, -6.0, 11.0, -6.0), vec![1.0, 2.0, 3.0]);
+        // (x - 1)(x^2 + 1)
+        roots_approx_equal(solve_cubic(2.0, -2.0, 2.0, -2.0), vec![1.0]);
+        // x^3
+        roots_approx_equal(solve_cubic(1.0, 0.0, 0.0, 0.0), vec![0.0]);
+    }
+
+    #[test]
+    fn polynomial_solve_quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        roots_approx_equal(
+            solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0),
+            vec![1.0, 2.0, 3.0, 4.0],
+        );
+        // (x^2 - 4)(x^2 + 1)
+        roots_approx_equal(solve_quartic(1.0, 0.0, -3.0, 0.0, -4.0), vec![-2.0, 2.0]);
+        // x^4 + 1
+        roots_approx_equal(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0), vec![]);
+        // x (x - 1)(x + 2)(x - 5)
+        roots_approx_equal(
+            solve_quartic(1.0, -4.0, -7.0, 10.0, 0.0),
+            vec![-2.0, 0.0, 1.0, 5.0],
+        );
+    }
+}
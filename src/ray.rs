@@ -4,11 +4,16 @@ use crate::vector::Vector;
 pub struct Ray {
     pub origin: Vector,
     pub direction: Vector,
+    pub time: f64,
 }
 
 impl Ray {
-    pub fn new(origin: Vector, direction: Vector) -> Ray {
-        Ray { origin, direction }
+    pub fn new(origin: Vector, direction: Vector, time: f64) -> Ray {
+        Ray {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn point_at_parameter(&self, t: f64) -> Vector {
@@ -35,23 +40,24 @@ mod tests {
 
     #[test]
     fn ray_new() {
-        let given = Ray::new(Vector::new(0.0, 1.0, 2.0), Vector::new(2.0, 1.0, 0.0));
+        let given = Ray::new(Vector::new(0.0, 1.0, 2.0), Vector::new(2.0, 1.0, 0.0), 0.5);
         let expected = Ray {
             origin: Vector::new(0.0, 1.0, 2.0),
             direction: Vector::new(2.0, 1.0, 0.0),
+            time: 0.5,
         };
         vec_approx_equal(given.origin, expected.origin);
         vec_approx_equal(given.direction, expected.direction);
+        assert_approx_eq!(given.time, expected.time);
     }
 
     // Methods
 
     #[test]
     fn ray_point_at_parameter() {
-        let given = Ray::new(Vector::new(0.0, 1.0, 2.0), Vector::new(1.0, 1.0, 1.0))
+        let given = Ray::new(Vector::new(0.0, 1.0, 2.0), Vector::new(1.0, 1.0, 1.0), 0.0)
             .point_at_parameter(0.5);
         let expected = Vector::new(0.5, 1.5, 2.5);
         vec_approx_equal(given, expected);
     }
-
 }
@@ -1,9 +1,11 @@
 use indicatif::{ProgressBar, ProgressStyle};
 use rand::Rng;
 
+use crate::media::Media;
 use crate::objects::Object;
 use crate::ray::Ray;
 use crate::scene::Scene;
+use crate::spectrum;
 use crate::vector::Vector;
 
 pub fn render(scene: Scene) -> Vec<u8> {
@@ -25,10 +27,19 @@ pub fn render(scene: Scene) -> Vec<u8> {
             for _ in 0..scene.samples {
                 let u = (f64::from(i) + rng.gen_range(0.0, 1.0)) / f64::from(scene.width);
                 let v = (f64::from(j) + rng.gen_range(0.0, 1.0)) / f64::from(scene.height);
-                let r = scene.camera.get_ray(u, v);
-                col += colour(&r, &scene.world, 0);
+                if let Some(r) = scene.camera.get_ray(u, v) {
+                    if scene.spectral {
+                        let wavelength = spectrum::sample_wavelength();
+                        let value =
+                            colour(&r, &scene.world, 0, &mut Media::new(), Some(wavelength));
+                        col += spectrum::to_rgb(value.x, wavelength);
+                    } else {
+                        col += colour(&r, &scene.world, 0, &mut Media::new(), None);
+                    }
+                }
             }
             col /= f64::from(scene.samples);
+            col *= scene.exposure;
             col = Vector::new(col[0].sqrt(), col[1].sqrt(), col[2].sqrt());
             let r = (255.99 * col[0]) as u8;
             let g = (255.99 * col[1]) as u8;
@@ -47,14 +58,27 @@ pub fn render(scene: Scene) -> Vec<u8> {
     data
 }
 
-fn colour(r: &Ray, world: &Object, depth: i32) -> Vector {
-    let hit = world.hit(r, 0.0001, std::f64::MAX);
+// Light arriving along the ray. Paths of a single `wavelength` carry the
+// value of its spectrum equally in every channel.
+fn colour(
+    r: &Ray,
+    world: &Object,
+    depth: i32,
+    media: &mut Media,
+    wavelength: Option<f64>,
+) -> Vector {
+    let hit = world.hit(r, 0.0001, f64::MAX);
 
     match hit {
         Some(hit_record) => {
             if depth < 50 {
-                let scatter = hit_record.material.scatter(r, &hit_record);
-                scatter.attenuation * colour(&scatter.ray, world, depth + 1)
+                let scatter = hit_record
+                    .material
+                    .scatter(r, &hit_record, media, wavelength);
+                if scatter.is_absorbed() {
+                    return Vector::new(0.0, 0.0, 0.0);
+                }
+                scatter.attenuation * colour(&scatter.ray, world, depth + 1, media, wavelength)
             } else {
                 Vector::new(0.0, 0.0, 0.0)
             }
@@ -62,7 +86,41 @@ fn colour(r: &Ray, world: &Object, depth: i32) -> Vector {
         None => {
             let unit_direction = r.direction.unit();
             let t = 0.5 * (unit_direction.y + 1.0);
-            (1.0 - t) * Vector::new(1.0, 1.0, 1.0) + t * Vector::new(0.5, 0.7, 1.0)
+            let sky = (1.0 - t) * Vector::new(1.0, 1.0, 1.0) + t * Vector::new(0.5, 0.7, 1.0);
+            match wavelength {
+                Some(wavelength) => {
+                    let value = spectrum::uplift(sky, wavelength);
+                    Vector::new(value, value, value)
+                }
+                None => sky,
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::{Camera, Exposure, Focus, Lens, Orientation, Sensor};
+    use crate::materials::Material;
+
+    #[test]
+    fn render_exposure() {
+        // Typical daylight settings keep the sky well above black
+        let orientation = Orientation::new(
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let lens = Lens::from_physical(50.0, Sensor::full_frame(), 8.0, Focus::Distance(1.0))
+            .with_exposure(Exposure::new(1.0 / 125.0, 100.0));
+        let camera = Camera::new(orientation, lens, 1.0);
+        let world = Object::new_sphere(
+            Vector::new(0.0, 0.0, 10.0),
+            1.0,
+            Material::new_lambertian(Vector::new(0.5, 0.5, 0.5)),
+        );
+        let given = render(Scene::new(1, 1, 4, camera, world));
+        assert!(given.iter().all(|&channel| channel > 200));
+    }
+}
@@ -7,16 +7,24 @@ pub struct Scene {
     pub samples: u32,
     pub camera: Camera,
     pub world: Object,
+    pub exposure: f64,
+    /// Trace paths of single wavelengths rather than RGB colours, so that
+    /// dispersive dielectrics split light into its colours.
+    pub spectral: bool,
 }
 
 impl Scene {
-    pub fn new(width: u32, height: u32, samples: u32, camera: Camera, world: Object) -> Scene {
+    pub fn new(width: u32, height: u32, samples: u32, mut camera: Camera, world: Object) -> Scene {
+        camera.autofocus(&world);
+        let exposure = camera.exposure();
         Scene {
             width,
             height,
             samples,
             camera,
             world,
+            exposure,
+            spectral: false,
         }
     }
 }
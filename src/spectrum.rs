@@ -0,0 +1,204 @@
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use crate::vector::Vector;
+
+// Conversion between RGB colours and spectra, for rendering paths which
+// each carry light of a single wavelength.
+
+/// Shortest wavelength sampled, in nanometres.
+pub const MIN_WAVELENGTH: f64 = 380.0;
+
+/// Longest wavelength sampled, in nanometres.
+pub const MAX_WAVELENGTH: f64 = 720.0;
+
+// Spectra of unit RGB colours at ten evenly spaced wavelength bins, from
+// Smits, "An RGB-to-Spectrum Conversion for Reflectances" (1999).
+const WHITE: [f64; 10] = [
+    1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000,
+];
+const CYAN: [f64; 10] = [
+    0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000,
+];
+const MAGENTA: [f64; 10] = [
+    1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959,
+];
+const YELLOW: [f64; 10] = [
+    0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840,
+];
+const RED: [f64; 10] = [
+    0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149,
+];
+const GREEN: [f64; 10] = [
+    0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025,
+];
+const BLUE: [f64; 10] = [
+    1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496,
+];
+
+/// Wavelength chosen uniformly at random over the visible range.
+pub fn sample_wavelength() -> f64 {
+    let mut rng = rand::thread_rng();
+    rng.gen_range(MIN_WAVELENGTH, MAX_WAVELENGTH)
+}
+
+/// Value at `wavelength` of a smooth spectrum with the given RGB `colour`,
+/// after Smits' method. Colours between black and white give spectra
+/// between 0 and 1.
+pub fn uplift(colour: Vector, wavelength: f64) -> f64 {
+    let bin = ((wavelength - MIN_WAVELENGTH) / (MAX_WAVELENGTH - MIN_WAVELENGTH) * 10.0)
+        .floor()
+        .clamp(0.0, 9.0) as usize;
+    let (r, g, b) = (colour.x, colour.y, colour.z);
+
+    // The smallest component is white, the middle one a secondary colour
+    // and the rest a primary colour
+    if r <= g && r <= b {
+        r * WHITE[bin]
+            + if g <= b {
+                (g - r) * CYAN[bin] + (b - g) * BLUE[bin]
+            } else {
+                (b - r) * CYAN[bin] + (g - b) * GREEN[bin]
+            }
+    } else if g <= r && g <= b {
+        g * WHITE[bin]
+            + if r <= b {
+                (r - g) * MAGENTA[bin] + (b - r) * BLUE[bin]
+            } else {
+                (b - g) * MAGENTA[bin] + (r - b) * RED[bin]
+            }
+    } else {
+        b * WHITE[bin]
+            + if r <= g {
+                (r - b) * YELLOW[bin] + (g - r) * GREEN[bin]
+            } else {
+                (g - b) * YELLOW[bin] + (r - g) * RED[bin]
+            }
+    }
+}
+
+/// CIE 1931 colour matching functions at `wavelength`, using the multi-lobe
+/// fit of Wyman, Sloan and Shirley, "Simple Analytic Approximations to the
+/// CIE XYZ Color Matching Functions" (2013).
+pub fn matching(wavelength: f64) -> Vector {
+    let g = |mu: f64, sigma1: f64, sigma2: f64| {
+        let t = (wavelength - mu) / if wavelength < mu { sigma1 } else { sigma2 };
+        (-0.5 * t * t).exp()
+    };
+    Vector::new(
+        1.056 * g(599.8, 37.9, 31.0) + 0.362 * g(442.0, 16.0, 26.7) - 0.065 * g(501.1, 20.4, 26.2),
+        0.821 * g(568.8, 46.9, 40.5) + 0.286 * g(530.9, 16.3, 31.1),
+        1.217 * g(437.0, 11.8, 36.0) + 0.681 * g(459.0, 26.0, 13.8),
+    )
+}
+
+/// Linear sRGB colour of CIE XYZ tristimulus values.
+pub fn xyz_to_rgb(xyz: Vector) -> Vector {
+    Vector::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    )
+}
+
+/// Estimate of the RGB colour of a spectrum from its `value` at a single
+/// `wavelength` sampled by `sample_wavelength`. Averaged over many
+/// wavelengths, a spectrum of 1 everywhere gives white.
+///
+/// Single wavelengths are more saturated than sRGB can show, so colours
+/// outside its gamut are clamped to it rather than given negative channels.
+pub fn to_rgb(value: f64, wavelength: f64) -> Vector {
+    static WHITE_RGB: OnceLock<Vector> = OnceLock::new();
+    let white = WHITE_RGB.get_or_init(|| {
+        let steps = 1000;
+        let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / f64::from(steps);
+        let mut total = Vector::new(0.0, 0.0, 0.0);
+        for i in 0..steps {
+            total += wavelength_rgb(MIN_WAVELENGTH + (f64::from(i) + 0.5) * step);
+        }
+        total * step
+    });
+
+    let rgb = wavelength_rgb(wavelength) * (MAX_WAVELENGTH - MIN_WAVELENGTH);
+    value * rgb / *white
+}
+
+// Linear sRGB colour of a single wavelength, clamped to the sRGB gamut.
+fn wavelength_rgb(wavelength: f64) -> Vector {
+    let rgb = xyz_to_rgb(matching(wavelength));
+    Vector::new(rgb.x.max(0.0), rgb.y.max(0.0), rgb.z.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    // Mean RGB colour of the spectrum of `colour`, sampled at evenly spaced
+    // wavelengths.
+    fn round_trip(colour: Vector) -> Vector {
+        let steps = 3400;
+        let step = (MAX_WAVELENGTH - MIN_WAVELENGTH) / f64::from(steps);
+        let mut total = Vector::new(0.0, 0.0, 0.0);
+        for i in 0..steps {
+            let wavelength = MIN_WAVELENGTH + (f64::from(i) + 0.5) * step;
+            total += to_rgb(uplift(colour, wavelength), wavelength);
+        }
+        total / f64::from(steps)
+    }
+
+    #[test]
+    fn spectrum_uplift() {
+        let red = Vector::new(1.0, 0.0, 0.0);
+        assert!(uplift(red, 650.0) > 0.9 && uplift(red, 450.0) < 0.1);
+        let blue = Vector::new(0.0, 0.0, 1.0);
+        assert!(uplift(blue, 650.0) < 0.1 && uplift(blue, 450.0) > 0.8);
+
+        // Grey is flat
+        assert_approx_eq!(uplift(Vector::new(0.5, 0.5, 0.5), 500.0), 0.5, 1e-3);
+    }
+
+    #[test]
+    fn spectrum_to_rgb() {
+        let given = round_trip(Vector::new(1.0, 1.0, 1.0));
+        assert_approx_eq!(given.x, 1.0, 1e-2);
+        assert_approx_eq!(given.y, 1.0, 1e-2);
+        assert_approx_eq!(given.z, 1.0, 1e-2);
+
+        // Primary colours keep their hue
+        let given = round_trip(Vector::new(0.8, 0.1, 0.1));
+        assert!(
+            given.x > 2.0 * given.y && given.x > 2.0 * given.z,
+            "{:?}",
+            given
+        );
+        let given = round_trip(Vector::new(0.1, 0.8, 0.1));
+        assert!(
+            given.y > 2.0 * given.x && given.y > 2.0 * given.z,
+            "{:?}",
+            given
+        );
+    }
+
+    #[test]
+    fn spectrum_to_rgb_saturated() {
+        // Blue and cyan wavelengths lie outside the sRGB gamut
+        for &wavelength in [450.0, 500.0].iter() {
+            let given = to_rgb(1.0, wavelength);
+            assert!(
+                given.x >= 0.0 && given.y >= 0.0 && given.z >= 0.0,
+                "{:?}",
+                given
+            );
+        }
+        assert!(xyz_to_rgb(matching(500.0)).x < 0.0);
+    }
+
+    #[test]
+    fn spectrum_matching() {
+        // Luminance peaks in the green
+        assert_approx_eq!(matching(555.0).y, 1.0, 0.02);
+        assert!(matching(450.0).z > matching(450.0).x);
+    }
+}
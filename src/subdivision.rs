@@ -0,0 +1,557 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::mesh::{self, Mesh};
+use crate::vector::Vector;
+
+// Subdivision surfaces, which refine a coarse control cage of polygons
+// towards a smooth limit surface.
+//
+// Creases follow DeRose et al., "Subdivision Surfaces in Character
+// Animation" (1998): an edge of sharpness `s` is subdivided with sharp
+// rules for `s` levels, blending towards the smooth rules for fractional
+// sharpness, before it smooths out. Boundary edges are infinitely sharp.
+
+/// Refinement rules applied at each level of subdivision.
+#[derive(Copy, Clone, Debug)]
+pub enum Scheme {
+    /// Loop's scheme, for cages of triangles.
+    Loop,
+    /// Catmull and Clark's scheme, for cages of quads. Other polygons are
+    /// split into quads by the first level.
+    CatmullClark,
+}
+
+/// Control mesh of polygons, wound counter-clockwise when seen from outside,
+/// with optionally creased edges.
+#[derive(Clone, Debug)]
+pub struct Cage {
+    pub positions: Vec<Vector>,
+    pub faces: Vec<Vec<usize>>,
+    creases: HashMap<(usize, usize), f64>,
+}
+
+// Construction
+impl Cage {
+    pub fn new(positions: Vec<Vector>, faces: Vec<Vec<usize>>) -> Cage {
+        Cage {
+            positions,
+            faces,
+            creases: HashMap::new(),
+        }
+    }
+
+    /// Read the vertices and polygons of a Wavefront OBJ file.
+    pub fn load_obj<P: AsRef<Path>>(path: P) -> io::Result<Cage> {
+        Cage::parse_obj(BufReader::new(File::open(path)?))
+    }
+
+    /// Parse the vertices and polygons of Wavefront OBJ source, ignoring any
+    /// UVs, normals and other statements.
+    pub fn parse_obj<R: BufRead>(reader: R) -> io::Result<Cage> {
+        let obj = mesh::read_obj(reader)?;
+        let faces = obj
+            .polygons
+            .iter()
+            .map(|polygon| polygon.iter().map(|corner| corner.0).collect())
+            .collect();
+        Ok(Cage::new(obj.positions, faces))
+    }
+}
+
+// Creases
+impl Cage {
+    /// Set the sharpness of the edge between vertices `a` and `b`. Use
+    /// `f64::INFINITY` for an edge which stays sharp at every level.
+    pub fn set_crease(&mut self, a: usize, b: usize, sharpness: f64) {
+        if sharpness > 0.0 {
+            self.creases.insert(edge(a, b), sharpness);
+        } else {
+            self.creases.remove(&edge(a, b));
+        }
+    }
+
+    pub fn crease(&self, a: usize, b: usize) -> f64 {
+        self.creases.get(&edge(a, b)).copied().unwrap_or(0.0)
+    }
+}
+
+// Subdivision
+impl Cage {
+    /// Subdivide `levels` times and split the result into triangles. Normals
+    /// are smoothed across every edge except those still creased.
+    ///
+    /// Panics if the Loop scheme is given a cage with other polygons than
+    /// triangles.
+    pub fn subdivide(&self, scheme: Scheme, levels: u32) -> Mesh {
+        if let Scheme::Loop = scheme {
+            assert!(
+                self.faces.iter().all(|face| face.len() == 3),
+                "Loop subdivision requires a cage of triangles!"
+            );
+        }
+
+        let mut cage = self.clone();
+        for _ in 0..levels {
+            let topology = Topology::new(&cage);
+            cage = match scheme {
+                Scheme::Loop => cage.loop_level(&topology),
+                Scheme::CatmullClark => cage.catmull_clark_level(&topology),
+            };
+        }
+        cage.to_mesh()
+    }
+
+    fn loop_level(&self, topology: &Topology) -> Cage {
+        let p = &self.positions;
+
+        let vertices = (0..p.len()).map(|v| {
+            let neighbours = topology.neighbours(v);
+            let n = neighbours.len() as f64;
+            let beta = (5.0 / 8.0 - (3.0 / 8.0 + (2.0 * PI / n).cos() / 4.0).powi(2)) / n;
+            let sum = neighbours
+                .iter()
+                .fold(Vector::new(0.0, 0.0, 0.0), |sum, &w| sum + p[w]);
+            let smooth = (1.0 - n * beta) * p[v] + beta * sum;
+            self.vertex_point(topology, v, smooth)
+        });
+
+        let edges = topology.edges.iter().enumerate().map(|(e, &(a, b))| {
+            let sharpness = self.sharpness(topology, e);
+            let midpoint = 0.5 * (p[a] + p[b]);
+            if sharpness >= 1.0 {
+                return midpoint;
+            }
+            let opposite = topology.edge_faces[e]
+                .iter()
+                .map(|&f| self.faces[f].iter().find(|&&w| w != a && w != b).unwrap())
+                .fold(Vector::new(0.0, 0.0, 0.0), |sum, &w| sum + p[w]);
+            let smooth = 3.0 / 8.0 * (p[a] + p[b]) + 1.0 / 8.0 * opposite;
+            blend(smooth, midpoint, sharpness)
+        });
+
+        let mut faces = vec![];
+        for face in self.faces.iter() {
+            let mid = |i: usize| p.len() + topology.edge(face[i], face[(i + 1) % 3]);
+            let (ab, bc, ca) = (mid(0), mid(1), mid(2));
+            faces.push(vec![face[0], ab, ca]);
+            faces.push(vec![face[1], bc, ab]);
+            faces.push(vec![face[2], ca, bc]);
+            faces.push(vec![ab, bc, ca]);
+        }
+
+        self.refined(topology, vertices.chain(edges).collect(), faces)
+    }
+
+    fn catmull_clark_level(&self, topology: &Topology) -> Cage {
+        let p = &self.positions;
+        let face_points: Vec<Vector> = self
+            .faces
+            .iter()
+            .map(|face| average(face.iter().map(|&v| p[v])))
+            .collect();
+
+        let vertices = (0..p.len()).map(|v| {
+            let n = topology.vertex_edges[v].len() as f64;
+            let q = average(topology.vertex_faces[v].iter().map(|&f| face_points[f]));
+            let r = average(topology.neighbours(v).iter().map(|&w| 0.5 * (p[v] + p[w])));
+            let smooth = (q + 2.0 * r + (n - 3.0) * p[v]) / n;
+            self.vertex_point(topology, v, smooth)
+        });
+
+        let edges = topology.edges.iter().enumerate().map(|(e, &(a, b))| {
+            let sharpness = self.sharpness(topology, e);
+            let midpoint = 0.5 * (p[a] + p[b]);
+            if sharpness >= 1.0 {
+                return midpoint;
+            }
+            let faces = topology.edge_faces[e].iter().map(|&f| face_points[f]);
+            let smooth = 0.5 * (midpoint + average(faces));
+            blend(smooth, midpoint, sharpness)
+        });
+
+        let mut faces = vec![];
+        let face_start = p.len() + topology.edges.len();
+        for (f, face) in self.faces.iter().enumerate() {
+            let n = face.len();
+            let mid = |i: usize| p.len() + topology.edge(face[i % n], face[(i + 1) % n]);
+            for (i, &v) in face.iter().enumerate() {
+                faces.push(vec![v, mid(i), face_start + f, mid(i + n - 1)]);
+            }
+        }
+
+        let positions = vertices.chain(edges).chain(face_points.clone()).collect();
+        self.refined(topology, positions, faces)
+    }
+
+    // Position of an existing vertex after a level, given its position under
+    // the smooth rule. Vertices on one creased edge are darts, which follow
+    // the smooth rule, on two follow the crease and on more are corners, as
+    // are the vertices of a single face, which keeps the corners of open
+    // cages in place.
+    fn vertex_point(&self, topology: &Topology, v: usize, smooth: Vector) -> Vector {
+        let p = &self.positions;
+        if topology.vertex_faces[v].len() == 1 {
+            return p[v];
+        }
+        let creased: Vec<(usize, f64)> = topology.vertex_edges[v]
+            .iter()
+            .map(|&e| (topology.other(e, v), self.sharpness(topology, e)))
+            .filter(|&(_, sharpness)| sharpness > 0.0)
+            .collect();
+
+        let sharp = match creased.len() {
+            0 | 1 => return smooth,
+            2 => (p[creased[0].0] + 6.0 * p[v] + p[creased[1].0]) / 8.0,
+            _ => p[v],
+        };
+        let sharpness =
+            creased.iter().map(|&(_, sharpness)| sharpness).sum::<f64>() / creased.len() as f64;
+        blend(smooth, sharp, sharpness)
+    }
+
+    // Cage of the next level, whose edge points follow the vertices and
+    // whose creases have each lost a level of sharpness.
+    fn refined(&self, topology: &Topology, positions: Vec<Vector>, faces: Vec<Vec<usize>>) -> Cage {
+        let mut cage = Cage::new(positions, faces);
+        for (&(a, b), &sharpness) in self.creases.iter() {
+            if let Some(&e) = topology.edge_index.get(&(a, b)) {
+                let mid = self.positions.len() + e;
+                cage.set_crease(a, mid, sharpness - 1.0);
+                cage.set_crease(mid, b, sharpness - 1.0);
+            }
+        }
+        cage
+    }
+
+    // Sharpness of edge `e`, where boundaries are infinitely sharp.
+    fn sharpness(&self, topology: &Topology, e: usize) -> f64 {
+        if topology.edge_faces[e].len() == 2 {
+            let (a, b) = topology.edges[e];
+            self.crease(a, b)
+        } else {
+            f64::INFINITY
+        }
+    }
+
+    // Split the faces into triangles, giving each vertex a separate copy for
+    // every group of faces around it which meet across uncreased edges.
+    fn to_mesh(&self) -> Mesh {
+        let mut triangles: Vec<[usize; 3]> = vec![];
+        for face in self.faces.iter() {
+            for i in 1..face.len() - 1 {
+                triangles.push([face[0], face[i], face[i + 1]]);
+            }
+        }
+
+        // Join the corners of triangles across each smooth edge
+        let mut groups = Groups::new(3 * triangles.len());
+        let mut edges: HashMap<(usize, usize), Vec<(usize, usize)>> = HashMap::new();
+        for (t, triangle) in triangles.iter().enumerate() {
+            for k in 0..3 {
+                let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+                let (first, second) = if a < b {
+                    (k, (k + 1) % 3)
+                } else {
+                    ((k + 1) % 3, k)
+                };
+                edges
+                    .entry(edge(a, b))
+                    .or_default()
+                    .push((3 * t + first, 3 * t + second));
+            }
+        }
+        for (&(a, b), corners) in edges.iter() {
+            if corners.len() == 2 && self.crease(a, b) == 0.0 {
+                groups.join(corners[0].0, corners[1].0);
+                groups.join(corners[0].1, corners[1].1);
+            }
+        }
+
+        let mut vertices: HashMap<usize, usize> = HashMap::new();
+        let mut positions = vec![];
+        let faces = triangles
+            .iter()
+            .enumerate()
+            .map(|(t, triangle)| {
+                let mut face = [0; 3];
+                for k in 0..3 {
+                    let group = groups.find(3 * t + k);
+                    face[k] = *vertices.entry(group).or_insert_with(|| {
+                        positions.push(self.positions[triangle[k]]);
+                        positions.len() - 1
+                    });
+                }
+                face
+            })
+            .collect();
+
+        let mut mesh = Mesh::new(positions, faces);
+        mesh.generate_normals();
+        mesh
+    }
+}
+
+// Adjacency between the vertices, edges and faces of a cage.
+struct Topology {
+    edges: Vec<(usize, usize)>,
+    edge_index: HashMap<(usize, usize), usize>,
+    edge_faces: Vec<Vec<usize>>,
+    vertex_edges: Vec<Vec<usize>>,
+    vertex_faces: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    fn new(cage: &Cage) -> Topology {
+        let mut topology = Topology {
+            edges: vec![],
+            edge_index: HashMap::new(),
+            edge_faces: vec![],
+            vertex_edges: vec![vec![]; cage.positions.len()],
+            vertex_faces: vec![vec![]; cage.positions.len()],
+        };
+
+        for (f, face) in cage.faces.iter().enumerate() {
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                let key = edge(a, b);
+                let e = match topology.edge_index.get(&key) {
+                    Some(&e) => e,
+                    None => {
+                        let e = topology.edges.len();
+                        topology.edges.push(key);
+                        topology.edge_index.insert(key, e);
+                        topology.edge_faces.push(vec![]);
+                        topology.vertex_edges[a].push(e);
+                        topology.vertex_edges[b].push(e);
+                        e
+                    }
+                };
+                topology.edge_faces[e].push(f);
+                topology.vertex_faces[a].push(f);
+            }
+        }
+        topology
+    }
+
+    fn edge(&self, a: usize, b: usize) -> usize {
+        self.edge_index[&edge(a, b)]
+    }
+
+    fn other(&self, e: usize, v: usize) -> usize {
+        let (a, b) = self.edges[e];
+        if a == v {
+            b
+        } else {
+            a
+        }
+    }
+
+    fn neighbours(&self, v: usize) -> Vec<usize> {
+        self.vertex_edges[v]
+            .iter()
+            .map(|&e| self.other(e, v))
+            .collect()
+    }
+}
+
+// Disjoint sets of triangle corners, by union-find.
+struct Groups {
+    parents: Vec<usize>,
+}
+
+impl Groups {
+    fn new(count: usize) -> Groups {
+        Groups {
+            parents: (0..count).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn join(&mut self, i: usize, j: usize) {
+        let (i, j) = (self.find(i), self.find(j));
+        self.parents[i] = j;
+    }
+}
+
+// Key of the undirected edge between `a` and `b`.
+fn edge(a: usize, b: usize) -> (usize, usize) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn average<I: Iterator<Item = Vector>>(points: I) -> Vector {
+    let (sum, count) = points.fold((Vector::new(0.0, 0.0, 0.0), 0), |(sum, count), p| {
+        (sum + p, count + 1)
+    });
+    sum / f64::from(count)
+}
+
+// Rule for an edge or vertex of the given sharpness, between the smooth and
+// sharp rules.
+fn blend(smooth: Vector, sharp: Vector, sharpness: f64) -> Vector {
+    if sharpness >= 1.0 {
+        sharp
+    } else {
+        smooth + sharpness * (sharp - smooth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn contains(mesh: &Mesh, p: Vector) -> bool {
+        mesh.positions.iter().any(|q| (*q - p).length() < 1e-9)
+    }
+
+    fn cube() -> Cage {
+        let positions = (0..8)
+            .map(|i| {
+                Vector::new(
+                    if i & 1 == 0 { -1.0 } else { 1.0 },
+                    if i & 2 == 0 { -1.0 } else { 1.0 },
+                    if i & 4 == 0 { -1.0 } else { 1.0 },
+                )
+            })
+            .collect();
+        let faces = vec![
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+        ];
+        Cage::new(positions, faces)
+    }
+
+    fn octahedron() -> Cage {
+        let positions = vec![
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, -1.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, -1.0),
+        ];
+        let faces = vec![
+            vec![0, 2, 4],
+            vec![2, 1, 4],
+            vec![1, 3, 4],
+            vec![3, 0, 4],
+            vec![2, 0, 5],
+            vec![1, 2, 5],
+            vec![3, 1, 5],
+            vec![0, 3, 5],
+        ];
+        Cage::new(positions, faces)
+    }
+
+    // Subdivision
+
+    #[test]
+    fn subdivision_loop() {
+        let given = octahedron().subdivide(Scheme::Loop, 1);
+        assert_eq!(given.faces.len(), 32);
+        assert_eq!(given.positions.len(), 18);
+
+        // Valence 4 vertices shrink by 1 - 4 beta, with beta = 31/256
+        assert!(contains(&given, Vector::new(33.0 / 64.0, 0.0, 0.0)));
+        assert!(contains(&given, Vector::new(3.0 / 8.0, 3.0 / 8.0, 0.0)));
+
+        let given = octahedron().subdivide(Scheme::Loop, 3);
+        assert_eq!(given.faces.len(), 8 * 64);
+    }
+
+    #[test]
+    fn subdivision_catmull_clark() {
+        let given = cube().subdivide(Scheme::CatmullClark, 1);
+        assert_eq!(given.faces.len(), 48);
+        assert_eq!(given.positions.len(), 26);
+        assert!(contains(&given, Vector::new(5.0, 5.0, 5.0) / 9.0));
+        assert!(contains(&given, Vector::new(0.75, 0.75, 0.0)));
+        assert!(contains(&given, Vector::new(1.0, 0.0, 0.0)));
+
+        // The smooth normal of a corner points diagonally outwards
+        let normals = given.normals.as_ref().unwrap();
+        let corner = given
+            .positions
+            .iter()
+            .position(|p| (*p - Vector::new(5.0, 5.0, 5.0) / 9.0).length() < 1e-9)
+            .unwrap();
+        let expected = Vector::new(1.0, 1.0, 1.0).unit();
+        assert_approx_eq!(Vector::dot(&normals[corner], &expected), 1.0);
+    }
+
+    #[test]
+    fn subdivision_creases() {
+        // Fully creased, the cube keeps its shape and its flat faces
+        let mut cage = cube();
+        for face in cage.faces.clone() {
+            for i in 0..4 {
+                cage.set_crease(face[i], face[(i + 1) % 4], f64::INFINITY);
+            }
+        }
+        let given = cage.subdivide(Scheme::CatmullClark, 2);
+        for (p, n) in given
+            .positions
+            .iter()
+            .zip(given.normals.as_ref().unwrap().iter())
+        {
+            assert_approx_eq!(p.x.abs().max(p.y.abs()).max(p.z.abs()), 1.0);
+            assert_approx_eq!(n.x.abs().max(n.y.abs()).max(n.z.abs()), 1.0);
+        }
+
+        // A half-sharp edge lies halfway between its smooth and sharp rules
+        let mut cage = cube();
+        cage.set_crease(3, 7, 0.5);
+        let given = cage.subdivide(Scheme::CatmullClark, 1);
+        assert!(contains(&given, Vector::new(0.875, 0.875, 0.0)));
+    }
+
+    #[test]
+    fn subdivision_boundary() {
+        // An open quad keeps its boundary as a straight edge
+        let positions = vec![
+            Vector::new(0.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(1.0, 1.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+        ];
+        let given = Cage::new(positions, vec![vec![0, 1, 2, 3]]).subdivide(Scheme::CatmullClark, 2);
+        assert!(contains(&given, Vector::new(0.0, 0.0, 0.0)));
+        assert!(contains(&given, Vector::new(0.25, 0.0, 0.0)));
+        for p in given.positions.iter() {
+            assert_approx_eq!(p.z, 0.0);
+        }
+    }
+
+    #[test]
+    fn subdivision_parse_obj() {
+        let source = "\
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+f 1/1 2/1 3/1 4/1
+";
+        let given = Cage::parse_obj(source.as_bytes()).unwrap();
+        assert_eq!(given.faces, vec![vec![0, 1, 2, 3]]);
+    }
+}
@@ -0,0 +1,109 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use image::GrayImage;
+
+use crate::objects::HitRecord;
+
+/// Value between 0 and 1 varying over a surface, such as a mask blending
+/// two materials.
+#[derive(Clone, Debug)]
+pub enum Texture {
+    Constant { value: f64 },
+    Checker { scale: f64 },
+    Image { image: Arc<GrayImage> },
+}
+
+impl Texture {
+    pub fn new_constant(value: f64) -> Texture {
+        Texture::Constant { value }
+    }
+
+    /// Alternating cubes of 0 and 1 filling space, with `scale` cubes per
+    /// unit length.
+    pub fn new_checker(scale: f64) -> Texture {
+        Texture::Checker { scale }
+    }
+
+    /// Greyscale `image` stretched once over the UV square, with black as 0
+    /// and white as 1.
+    pub fn new_image(image: GrayImage) -> Texture {
+        Texture::Image {
+            image: Arc::new(image),
+        }
+    }
+
+    pub fn value(&self, hit: &HitRecord) -> f64 {
+        match self {
+            Texture::Constant { value } => *value,
+            Texture::Checker { scale } => {
+                let p = PI * *scale * hit.p;
+                if p.x.sin() * p.y.sin() * p.z.sin() < 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            Texture::Image { image } => {
+                // Nearest pixel, with v increasing up the image and the UVs
+                // repeating outside the square
+                let (width, height) = (image.width(), image.height());
+                let x = (hit.u * f64::from(width)).floor() as i64;
+                let y = ((1.0 - hit.v) * f64::from(height)).floor() as i64;
+                let x = x.rem_euclid(i64::from(width)) as u32;
+                let y = y.rem_euclid(i64::from(height)) as u32;
+                f64::from(image.get_pixel(x, y).data[0]) / 255.0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::materials::Material;
+    use crate::vector::Vector;
+    use assert_approx_eq::assert_approx_eq;
+
+    fn value_at(texture: &Texture, p: Vector, u: f64, v: f64) -> f64 {
+        let normal = Vector::new(0.0, 1.0, 0.0);
+        let material = Material::new_lambertian(Vector::new(0.5, 0.5, 0.5));
+        texture.value(&HitRecord::new(1.0, p, normal, u, v, &material))
+    }
+
+    #[test]
+    fn texture_constant() {
+        let given = value_at(
+            &Texture::new_constant(0.25),
+            Vector::new(1.0, 2.0, 3.0),
+            0.5,
+            0.5,
+        );
+        assert_approx_eq!(given, 0.25);
+    }
+
+    #[test]
+    fn texture_checker() {
+        let texture = Texture::new_checker(2.0);
+        let given = |x| value_at(&texture, Vector::new(x, 0.25, 0.25), 0.0, 0.0);
+        assert_approx_eq!(given(0.25), 0.0);
+        assert_approx_eq!(given(0.75), 1.0);
+        assert_approx_eq!(given(1.25), 0.0);
+        assert_approx_eq!(given(-0.25), 1.0);
+    }
+
+    #[test]
+    fn texture_image() {
+        // Black at the top left, white elsewhere
+        let mut image = GrayImage::from_pixel(2, 2, image::Luma([255]));
+        image.put_pixel(0, 0, image::Luma([0]));
+        let texture = Texture::new_image(image);
+        let given = |u, v| value_at(&texture, Vector::new(0.0, 0.0, 0.0), u, v);
+        assert_approx_eq!(given(0.25, 0.75), 0.0);
+        assert_approx_eq!(given(0.75, 0.75), 1.0);
+        assert_approx_eq!(given(0.25, 0.25), 1.0);
+
+        // Repeated outside the UV square
+        assert_approx_eq!(given(1.25, 1.75), 0.0);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::vector::Vector;
+
+/// Displacement over time, interpolated linearly between keyframes.
+///
+/// Before the first keyframe and after the last, the displacement is held at
+/// the value of that keyframe.
+#[derive(Clone, Debug)]
+pub struct Trajectory {
+    keyframes: Vec<(f64, Vector)>,
+}
+
+impl Trajectory {
+    /// Construct a trajectory from `(time, displacement)` keyframes, which
+    /// need not be given in order.
+    pub fn new(mut keyframes: Vec<(f64, Vector)>) -> Trajectory {
+        assert!(!keyframes.is_empty(), "Trajectory requires a keyframe!");
+        assert!(
+            keyframes.iter().all(|(time, _)| time.is_finite()),
+            "Trajectory requires finite keyframe times!"
+        );
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Trajectory { keyframes }
+    }
+
+    /// Constant velocity movement from `from` at `time0` to `to` at `time1`.
+    pub fn linear(time0: f64, from: Vector, time1: f64, to: Vector) -> Trajectory {
+        Trajectory::new(vec![(time0, from), (time1, to)])
+    }
+
+    pub fn at(&self, time: f64) -> Vector {
+        let (first_time, first) = self.keyframes[0];
+        if time <= first_time {
+            return first;
+        }
+
+        for window in self.keyframes.windows(2) {
+            let (time0, from) = window[0];
+            let (time1, to) = window[1];
+            if time <= time1 {
+                let s = (time - time0) / (time1 - time0);
+                return from + s * (to - from);
+            }
+        }
+
+        self.keyframes[self.keyframes.len() - 1].1
+    }
+
+    /// Displacements whose convex hull contains the whole trajectory between
+    /// `time0` and `time1`.
+    pub fn extremes(&self, time0: f64, time1: f64) -> Vec<Vector> {
+        let mut extremes = vec![self.at(time0), self.at(time1)];
+        for &(time, displacement) in self.keyframes.iter() {
+            if time > time0 && time < time1 {
+                extremes.push(displacement);
+            }
+        }
+        extremes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_approx_eq::assert_approx_eq;
+
+    // Utility function to check the approximate equality of two vectors.
+    //
+    // Direct equality comparison between two vectors is unavailable due to
+    // the underlying float representation.
+    fn vec_approx_equal(v1: Vector, v2: Vector) {
+        assert_approx_eq!(v1.x, v2.x);
+        assert_approx_eq!(v1.y, v2.y);
+        assert_approx_eq!(v1.z, v2.z);
+    }
+
+    // Construction
+
+    #[test]
+    #[should_panic]
+    fn trajectory_new_empty() {
+        let _ = Trajectory::new(vec![]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn trajectory_new_nan_time() {
+        let _ = Trajectory::new(vec![
+            (f64::NAN, Vector::new(5.0, 0.0, 0.0)),
+            (1.0, Vector::new(1.0, 0.0, 0.0)),
+        ]);
+    }
+
+    // Methods
+
+    #[test]
+    fn trajectory_linear_at() {
+        let given = Trajectory::linear(
+            0.0,
+            Vector::new(0.0, 0.0, 0.0),
+            2.0,
+            Vector::new(2.0, 4.0, 0.0),
+        );
+        vec_approx_equal(given.at(0.5), Vector::new(0.5, 1.0, 0.0));
+        vec_approx_equal(given.at(-1.0), Vector::new(0.0, 0.0, 0.0));
+        vec_approx_equal(given.at(3.0), Vector::new(2.0, 4.0, 0.0));
+    }
+
+    #[test]
+    fn trajectory_keyframes_at() {
+        let given = Trajectory::new(vec![
+            (1.0, Vector::new(1.0, 0.0, 0.0)),
+            (0.0, Vector::new(0.0, 0.0, 0.0)),
+            (2.0, Vector::new(1.0, 2.0, 0.0)),
+        ]);
+        vec_approx_equal(given.at(0.5), Vector::new(0.5, 0.0, 0.0));
+        vec_approx_equal(given.at(1.0), Vector::new(1.0, 0.0, 0.0));
+        vec_approx_equal(given.at(1.25), Vector::new(1.0, 0.5, 0.0));
+    }
+
+    #[test]
+    fn trajectory_extremes() {
+        let given = Trajectory::new(vec![
+            (0.0, Vector::new(0.0, 0.0, 0.0)),
+            (1.0, Vector::new(1.0, 0.0, 0.0)),
+            (2.0, Vector::new(1.0, 2.0, 0.0)),
+        ])
+        .extremes(0.5, 1.5);
+        assert_eq!(given.len(), 3);
+        vec_approx_equal(given[0], Vector::new(0.5, 0.0, 0.0));
+        vec_approx_equal(given[1], Vector::new(1.0, 1.0, 0.0));
+        vec_approx_equal(given[2], Vector::new(1.0, 0.0, 0.0));
+    }
+}
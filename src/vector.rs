@@ -45,9 +45,22 @@ impl Vector {
         }
     }
 
+    /// Two unit vectors which, with this unit vector, form an orthonormal
+    /// basis.
+    pub fn tangents(&self) -> (Vector, Vector) {
+        // Branchless construction from Duff et al. (2017)
+        let sign = 1.0_f64.copysign(self.z);
+        let a = -1.0 / (sign + self.z);
+        let b = self.x * self.y * a;
+        (
+            Vector::new(1.0 + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Vector::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
+
     /// Generate random Vector within a unit sphere.
     pub fn random() -> Vector {
-        let mut point = Vector::new(std::f64::MAX, std::f64::MAX, std::f64::MAX);
+        let mut point = Vector::new(f64::MAX, f64::MAX, f64::MAX);
         let mut rng = rand::thread_rng();
         loop {
             if point.squared_length() < 1.0 {
@@ -334,6 +347,26 @@ mod tests {
         vec_approx_equal(given, expected);
     }
 
+    #[test]
+    fn vector_tangents() {
+        for n in [
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(0.0, 0.0, -1.0),
+            Vector::new(1.0, 2.0, 3.0).unit(),
+            Vector::new(-3.0, 1.0, -0.5).unit(),
+        ]
+        .iter()
+        {
+            let (t, b) = n.tangents();
+            assert_approx_eq!(t.length(), 1.0);
+            assert_approx_eq!(b.length(), 1.0);
+            assert_approx_eq!(Vector::dot(&t, n), 0.0);
+            assert_approx_eq!(Vector::dot(&b, n), 0.0);
+            assert_approx_eq!(Vector::dot(&t, &b), 0.0);
+            vec_approx_equal(Vector::cross(&t, &b), *n);
+        }
+    }
+
     // Operator overloading (vector only)
 
     #[test]